@@ -0,0 +1,248 @@
+use builder_agent::report::CacheStats;
+use serde_json::Value;
+use srcinfo::{ArchVec, Srcinfo};
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use crate::{file_len, in_clean_chroot};
+
+const PACMAN_LOG: &str = "/var/log/pacman.log";
+
+/// Returns the names of the entries of a cache directory
+fn list_entries(dir: &Path) -> HashSet<String> {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Returns the name `makepkg` stores a source under in `SRCDEST`, `None` for local files.
+fn source_filename(source: &str) -> Option<String> {
+    if let Some((name, _)) = source.split_once("::") {
+        return Some(name.to_string());
+    }
+    let (protocol, _) = source.split_once("://")?;
+    let url = source.split(['#', '?']).next().unwrap_or(source);
+    let name = url.trim_end_matches('/').rsplit('/').next()?;
+    let vcs = ["bzr", "fossil", "git", "hg", "svn"]
+        .iter()
+        .any(|v| protocol.split('+').next() == Some(v));
+    match vcs {
+        true => Some(name.split(".git").next().unwrap_or(name).to_string()),
+        false => Some(name.to_string()),
+    }
+}
+
+/// Returns name and version of every package `pacman.log` reports as installed
+fn installed_packages(log: &str) -> Vec<(String, String)> {
+    log.lines()
+        .filter_map(|line| line.split_once("[ALPM] ").map(|(_, entry)| entry))
+        .filter_map(|entry| {
+            let mut words = entry.splitn(3, ' ');
+            let action = words.next()?;
+            if !matches!(action, "installed" | "upgraded" | "reinstalled" | "downgraded") {
+                return None;
+            }
+            let name = words.next()?;
+            let versions = words.next()?.trim_start_matches('(').trim_end_matches(')');
+            let version = versions.rsplit(" -> ").next()?;
+            Some((name.to_string(), version.to_string()))
+        })
+        .collect()
+}
+
+/// Returns whether `file` is the package file of the given package version
+fn is_package_file(file: &str, name: &str, version: &str) -> bool {
+    file.strip_prefix(&format!("{name}-{version}-"))
+        .is_some_and(|rest| !rest.contains('-') && rest.contains(".pkg.tar") && !rest.ends_with(".sig"))
+}
+
+/// Sets the modification time of cache entries a build used to now, the worker evicts the least recently used ones.
+///
+/// Runs `touch` with `sudo` since the packages downloaded by pacman belong to root.
+fn mark_used(paths: Vec<PathBuf>) {
+    if paths.is_empty() {
+        return;
+    }
+    if let Err(e) = Command::new("sudo").args(["touch", "-c", "-m"]).args(&paths).status() {
+        eprintln!("WARNING: failed to mark cache entries as used: {e}");
+    }
+}
+
+/// Counters of ccache, read from its machine readable statistics
+fn ccache_counters(dir: &str) -> HashMap<String, u64> {
+    let Ok(output) = Command::new("ccache")
+        .arg("--print-stats")
+        .env("CCACHE_DIR", dir)
+        .output()
+    else {
+        return HashMap::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .filter_map(|(key, value)| Some((key.to_string(), value.trim().parse().ok()?)))
+        .collect()
+}
+
+/// Sums the per-language counts of an sccache statistic
+fn sccache_count(stats: &Value, key: &str) -> u64 {
+    stats["stats"][key]["counts"]
+        .as_object()
+        .map(|counts| counts.values().filter_map(|c| c.as_u64()).sum())
+        .unwrap_or(0)
+}
+
+/// The caches mounted by the worker and their state at the start of the build
+pub struct CacheUsage {
+    sources: Option<(PathBuf, HashSet<String>)>,
+    packages: Option<(PathBuf, HashSet<String>)>,
+    /// `pacman.log` files and the length they had before the build touched them
+    pacman_logs: Vec<(PathBuf, u64)>,
+    ccache: Option<(String, HashMap<String, u64>)>,
+    /// Directory of the PKGBUILD, its sources are the ones looked up in `SRCDEST`
+    pub package_dir: Option<PathBuf>,
+}
+
+impl CacheUsage {
+    /// Takes a snapshot of every cache the worker passed to the container
+    pub fn start() -> CacheUsage {
+        let snapshot = |var: &str| {
+            env::var(var)
+                .ok()
+                .filter(|dir| !dir.is_empty())
+                .map(|dir| {
+                    let entries = list_entries(Path::new(&dir));
+                    (PathBuf::from(dir), entries)
+                })
+        };
+        let packages = snapshot("AB_PACKAGE_CACHE");
+        let pacman_logs = match packages {
+            Some(_) => vec![(PathBuf::from(PACMAN_LOG), file_len(Path::new(PACMAN_LOG)))],
+            None => Vec::new(),
+        };
+        CacheUsage {
+            sources: snapshot("SRCDEST"),
+            packages,
+            pacman_logs,
+            ccache: compiler_cache_dir("AB_CCACHE_DIR").map(|dir| {
+                let counters = ccache_counters(&dir);
+                (dir, counters)
+            }),
+            package_dir: None,
+        }
+    }
+
+    /// Counts the installations logged to `log` after its first `offset` bytes, e.g. the ones inside a chroot
+    pub fn watch_log(&mut self, log: PathBuf, offset: u64) {
+        if self.packages.is_some() {
+            self.pacman_logs.push((log, offset));
+        }
+    }
+
+    /// Returns the hits and misses of every cache since the start of the build.
+    ///
+    /// A source or package that was in the cache before is a hit, one that was added during the build a miss.
+    pub fn finish(self, srcinfo: Option<&Srcinfo>) -> Vec<CacheStats> {
+        let mut stats = Vec::new();
+
+        if let (Some((dir, before)), Some(srcinfo)) = (&self.sources, srcinfo) {
+            let after = list_entries(dir);
+            let required: HashSet<String> = ArchVec::active(&srcinfo.base.source, env::consts::ARCH)
+                .filter_map(source_filename)
+                .collect();
+            mark_used(required.iter().filter(|s| before.contains(*s)).map(|s| dir.join(s)).collect());
+            stats.push(CacheStats {
+                cache: "sources".to_string(),
+                hits: required.iter().filter(|s| before.contains(*s)).count() as u64,
+                misses: required
+                    .iter()
+                    .filter(|s| !before.contains(*s) && after.contains(*s))
+                    .count() as u64,
+            });
+        }
+
+        if let Some((dir, before)) = &self.packages {
+            let after = list_entries(dir);
+            let mut hits = 0;
+            let mut misses = 0;
+            let mut used = Vec::new();
+            for (log, offset) in &self.pacman_logs {
+                let Ok(content) = fs::read(log) else {
+                    continue;
+                };
+                let start = (*offset as usize).min(content.len());
+                for (name, version) in installed_packages(&String::from_utf8_lossy(&content[start..])) {
+                    if let Some(file) = before.iter().find(|f| is_package_file(f, &name, &version)) {
+                        hits += 1;
+                        used.push(dir.join(file));
+                        let signature = format!("{file}.sig");
+                        if before.contains(&signature) {
+                            used.push(dir.join(signature));
+                        }
+                    } else if after.iter().any(|f| is_package_file(f, &name, &version)) {
+                        misses += 1;
+                    }
+                }
+            }
+            mark_used(used);
+            stats.push(CacheStats {
+                cache: "packages".to_string(),
+                hits,
+                misses,
+            });
+        }
+
+        if let Some((dir, before)) = &self.ccache {
+            let after = ccache_counters(dir);
+            let diff = |key: &str| {
+                after.get(key).copied().unwrap_or(0).saturating_sub(before.get(key).copied().unwrap_or(0))
+            };
+            stats.push(CacheStats {
+                cache: "ccache".to_string(),
+                hits: diff("direct_cache_hit") + diff("preprocessed_cache_hit"),
+                misses: diff("cache_miss"),
+            });
+        }
+
+        if compiler_cache_dir("AB_SCCACHE_DIR").is_some()
+            && let Ok(output) = Command::new("sccache")
+                .args(["--show-stats", "--stats-format=json"])
+                .output()
+            && let Ok(json) = serde_json::from_slice::<Value>(&output.stdout)
+        {
+            // the sccache server is started by the build, so its statistics only cover this build
+            stats.push(CacheStats {
+                cache: "sccache".to_string(),
+                hits: sccache_count(&json, "cache_hits"),
+                misses: sccache_count(&json, "cache_misses"),
+            });
+        }
+
+        stats
+    }
+}
+
+/// Returns the directory of a compiler cache, which is only used outside of a clean chroot
+pub fn compiler_cache_dir(var: &str) -> Option<String> {
+    env::var(var).ok().filter(|dir| !dir.is_empty() && !in_clean_chroot())
+}
+
+/// Returns the environment `makepkg` needs to use the compiler caches
+pub fn compiler_cache_env() -> Vec<(&'static str, String)> {
+    let mut vars = Vec::new();
+    if let Some(dir) = compiler_cache_dir("AB_CCACHE_DIR") {
+        vars.push(("CCACHE_DIR", dir));
+    }
+    if let Some(dir) = compiler_cache_dir("AB_SCCACHE_DIR") {
+        vars.push(("SCCACHE_DIR", dir));
+        vars.push(("RUSTC_WRAPPER", "sccache".to_string()));
+    }
+    vars
+}
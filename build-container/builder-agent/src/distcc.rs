@@ -0,0 +1,46 @@
+use builder_agent::report::DistccStats;
+use crate::in_clean_chroot;
+use std::env;
+use std::fs;
+
+/// Log of the distcc client, its info messages tell where each job was compiled
+const DISTCC_LOG: &str = "/tmp/distcc.log";
+
+/// Returns the hosts compile jobs are distributed to, distcc isn't available in a clean chroot
+pub fn distcc_hosts() -> Option<String> {
+    env::var("DISTCC_HOSTS")
+        .ok()
+        .filter(|hosts| !hosts.is_empty() && !in_clean_chroot())
+}
+
+/// Returns the environment `makepkg` needs to log the distributed jobs
+pub fn distcc_env() -> Vec<(&'static str, String)> {
+    match distcc_hosts() {
+        Some(_) => vec![
+            ("DISTCC_LOG", DISTCC_LOG.to_string()),
+            ("DISTCC_VERBOSE", "1".to_string()),
+        ],
+        None => Vec::new(),
+    }
+}
+
+/// Counts the jobs compiled on the helpers and locally, from lines like
+/// `compile foo.c on 10.0.0.2:3632/8 completed ok` and `failed to distribute foo.c ..., running locally instead`
+pub fn distcc_stats() -> Option<DistccStats> {
+    distcc_hosts()?;
+    let log = fs::read_to_string(DISTCC_LOG).unwrap_or_default();
+    let mut stats = DistccStats::default();
+    for line in log.lines() {
+        if line.contains("running locally instead") {
+            stats.local += 1;
+        } else if let Some((_, host)) = line.rsplit_once(" on ")
+            && let Some(host) = host.strip_suffix(" completed ok")
+        {
+            match host.starts_with("localhost") {
+                true => stats.local += 1,
+                false => stats.remote += 1,
+            }
+        }
+    }
+    Some(stats)
+}
@@ -0,0 +1,6 @@
+//! Types shared between the builder agent running inside the build container and the worker.
+
+pub mod lint;
+pub mod plan;
+pub mod repro;
+pub mod report;
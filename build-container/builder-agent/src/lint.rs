@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+
+/// How serious a namcap finding is
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LintSeverity {
+    Error,
+    Warning,
+    Info,
+}
+
+impl LintSeverity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LintSeverity::Error => "error",
+            LintSeverity::Warning => "warning",
+            LintSeverity::Info => "info",
+        }
+    }
+}
+
+/// A single finding of namcap
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LintFinding {
+    /// The checked package, or `PKGBUILD (<pkgbase>)` for the PKGBUILD
+    pub target: String,
+    pub severity: LintSeverity,
+    /// Tag of the check, e.g. `dependency-detected-not-included`
+    pub tag: String,
+    /// The arguments of the tag, like the dependency and the files needing it
+    pub message: String,
+}
+
+/// Parses a line of the machine readable output of `namcap -m`.
+///
+/// # Example
+///
+/// ```
+/// use builder_agent::lint::{LintSeverity, parse_namcap_line};
+/// let finding = parse_namcap_line("foo E: dependency-detected-not-included bar (libraries ['usr/lib/libbar.so'])").unwrap();
+/// assert_eq!(finding.target, "foo");
+/// assert_eq!(finding.severity, LintSeverity::Error);
+/// assert_eq!(finding.tag, "dependency-detected-not-included");
+/// assert_eq!(finding.message, "bar (libraries ['usr/lib/libbar.so'])");
+///
+/// let finding = parse_namcap_line("PKGBUILD (foo) W: missing-contributor").unwrap();
+/// assert_eq!(finding.target, "PKGBUILD (foo)");
+/// assert_eq!(finding.message, "");
+/// ```
+pub fn parse_namcap_line(line: &str) -> Option<LintFinding> {
+    [
+        (" E: ", LintSeverity::Error),
+        (" W: ", LintSeverity::Warning),
+        (" I: ", LintSeverity::Info),
+    ]
+    .into_iter()
+    .filter_map(|(marker, severity)| line.find(marker).map(|at| (at, marker, severity)))
+    .min_by_key(|(at, _, _)| *at)
+    .and_then(|(at, marker, severity)| {
+        let rest = line[at + marker.len()..].trim();
+        let (tag, message) = rest.split_once(' ').unwrap_or((rest, ""));
+        (!tag.is_empty()).then(|| LintFinding {
+            target: line[..at].to_string(),
+            severity,
+            tag: tag.to_string(),
+            message: message.trim().to_string(),
+        })
+    })
+}
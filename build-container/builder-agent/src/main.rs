@@ -0,0 +1,663 @@
+mod cache;
+mod distcc;
+mod verify;
+
+use crate::cache::{CacheUsage, compiler_cache_dir, compiler_cache_env};
+use crate::distcc::{distcc_env, distcc_hosts, distcc_stats};
+use crate::verify::{fetch_originals, rebuild_and_compare, verify_files};
+use builder_agent::lint::{LintFinding, parse_namcap_line};
+use builder_agent::plan::{
+    DependencySource, InstallPlan, PlannedDependency, collect_dependencies, dependency_name,
+};
+use builder_agent::report::{
+    BuildReport, Phase, PhaseReport, ProducedFile, REPORT_PATH, file_sha256, sha256_hex,
+};
+use std::env;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio, exit};
+use std::str::FromStr;
+use srcinfo::Srcinfo;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const RESULTS_DIR: &str = "/results";
+const PACMAN_CONF: &str = "/etc/pacman.conf";
+const SYNC_DB_DIR: &str = "/var/lib/pacman/sync";
+
+/// Unprivileged user that runs the code of the package
+const BUILDER_USER: &str = "builder";
+
+/// Grants `builder` passwordless sudo, removed when the agent runs as root
+const BUILDER_SUDOERS: &str = "/etc/sudoers.d/builder";
+
+/// Name of the working copy `makechrootpkg` creates next to the base chroot
+const CHROOT_COPY: &str = "build";
+
+/// Error of a build phase, carrying the exit code of the container
+struct PhaseError {
+    code: i32,
+    item: Option<String>,
+}
+
+impl PhaseError {
+    fn new(code: i32) -> PhaseError {
+        PhaseError { code, item: None }
+    }
+
+    fn with_item(code: i32, item: &str) -> PhaseError {
+        PhaseError {
+            code,
+            item: Some(item.to_string()),
+        }
+    }
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
+fn run_phase<T>(
+    report: &mut BuildReport,
+    phase: Phase,
+    f: impl FnOnce() -> Result<T, PhaseError>,
+) -> Result<T, i32> {
+    println!("INFO: starting phase '{}'", phase.as_str());
+    let started_at = now_millis();
+    let result = f();
+    let (exit_status, failing_item) = match &result {
+        Ok(_) => (0, None),
+        Err(e) => (e.code, e.item.clone()),
+    };
+    report.phases.push(PhaseReport {
+        phase,
+        started_at,
+        finished_at: now_millis(),
+        exit_status,
+        failing_item,
+    });
+    result.map_err(|e| e.code)
+}
+
+fn require_env(name: &str) -> Result<String, i32> {
+    match env::var(name) {
+        Ok(value) if !value.is_empty() => Ok(value),
+        _ => {
+            eprintln!("ERROR: {name} is not set.");
+            Err(101)
+        }
+    }
+}
+
+/// Runs a command with inherited output and returns whether it succeeded
+fn run_command(command: &mut Command) -> bool {
+    match command.status() {
+        Ok(status) => status.success(),
+        Err(e) => {
+            eprintln!("ERROR: failed to run {:?}: {e}", command.get_program());
+            false
+        }
+    }
+}
+
+/// Returns whether the agent runs as root, which the worker does for builds that need `systemd-nspawn`
+fn running_as_root() -> bool {
+    fs::metadata("/proc/self").is_ok_and(|m| m.uid() == 0)
+}
+
+/// Removes the sudo rights of `builder` when running as root, so package code can't use them to become root
+fn drop_builder_sudo() -> Result<(), i32> {
+    if !running_as_root() {
+        return Ok(());
+    }
+    match fs::remove_file(BUILDER_SUDOERS) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+        Err(e) => {
+            eprintln!("ERROR: failed to remove the sudo rights of {BUILDER_USER}: {e}");
+            Err(100)
+        }
+    }
+}
+
+/// Returns a command for `program`, which evaluates code of the package.
+///
+/// As root, the command runs as `builder` and can't gain privileges again, neither through sudo nor setuid binaries.
+fn package_command(program: &str) -> Command {
+    if !running_as_root() {
+        return Command::new(program);
+    }
+    let mut command = Command::new("setpriv");
+    command
+        .arg(format!("--reuid={BUILDER_USER}"))
+        .arg(format!("--regid={BUILDER_USER}"))
+        .args(["--init-groups", "--no-new-privs", "--bounding-set=-all", "--"])
+        .arg(program);
+    command
+}
+
+fn clone_source(home: &Path, source: &str) -> Result<(), PhaseError> {
+    let cloned = run_command(
+        package_command("git")
+            .arg("clone")
+            .arg(source)
+            .arg("source")
+            .current_dir(home),
+    );
+    if !cloned {
+        return Err(PhaseError::new(102));
+    }
+    Ok(())
+}
+
+/// Reads the `.SRCINFO` of the package in `dir`
+fn read_srcinfo(dir: &Path) -> Result<Srcinfo, PhaseError> {
+    let output = package_command("makepkg")
+        .arg("--printsrcinfo")
+        .current_dir(dir)
+        .output()
+        .map_err(|_| PhaseError::with_item(104, ".SRCINFO"))?;
+    if !output.status.success() {
+        return Err(PhaseError::with_item(104, ".SRCINFO"));
+    }
+    Srcinfo::from_str(&String::from_utf8_lossy(&output.stdout)).map_err(|e| {
+        eprintln!("ERROR: invalid .SRCINFO: {e}");
+        PhaseError::with_item(104, ".SRCINFO")
+    })
+}
+
+/// Returns a `pacman` command using the configuration and databases of the system at `root`
+fn pacman_at(root: &Path) -> Command {
+    let mut command = Command::new("pacman");
+    command
+        .arg("--config")
+        .arg(root.join(PACMAN_CONF.trim_start_matches('/')))
+        .arg("--dbpath")
+        .arg(root.join("var/lib/pacman"));
+    command
+}
+
+/// Returns the dependencies pacman can't satisfy with the packages installed at `root`
+fn unsatisfied_dependencies(root: &Path, dependencies: &[String]) -> Result<Vec<String>, PhaseError> {
+    if dependencies.is_empty() {
+        return Ok(Vec::new());
+    }
+    let output = pacman_at(root)
+        .arg("-T")
+        .args(dependencies)
+        .output()
+        .map_err(|_| PhaseError::new(104))?;
+    // 127 means that some dependencies are missing
+    if !output.status.success() && output.status.code() != Some(127) {
+        return Err(PhaseError::new(104));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect())
+}
+
+/// Returns the repository of the package satisfying `dependency`, if any of the sync databases at `root` has one
+fn find_repository(root: &Path, dependency: &str) -> Option<String> {
+    let output = pacman_at(root)
+        .args(["-Sp", "--print-format", "%r", dependency])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .last()
+        .map(|line| line.trim().to_string())
+}
+
+/// Decides for every dependency of the package in `dir` whether and where it's installed from into the system at `root`
+fn plan_dependencies(
+    dir: &Path,
+    options: &str,
+    local_repo: &Option<String>,
+    root: &Path,
+) -> Result<InstallPlan, PhaseError> {
+    let srcinfo = read_srcinfo(dir)?;
+    let check = !options.split_whitespace().any(|o| o == "--nocheck");
+    let dependencies = collect_dependencies(&srcinfo, env::consts::ARCH, check);
+    let names: Vec<String> = dependencies.iter().map(|(d, _)| d.clone()).collect();
+    let unsatisfied = unsatisfied_dependencies(root, &names)?;
+
+    let dependencies = dependencies
+        .into_iter()
+        .map(|(dependency, kind)| {
+            let (source, repo) = if !unsatisfied.contains(&dependency) {
+                (DependencySource::Installed, None)
+            } else {
+                match find_repository(root, &dependency) {
+                    Some(repo) if local_repo.as_ref() == Some(&repo) => (DependencySource::Local, Some(repo)),
+                    Some(repo) => (DependencySource::Repo, Some(repo)),
+                    None => (DependencySource::Aur, None),
+                }
+            };
+            PlannedDependency { dependency, kind, source, repo }
+        })
+        .collect();
+    Ok(InstallPlan { dependencies })
+}
+
+fn print_plan(plan: &InstallPlan) {
+    println!("INFO: install plan:");
+    for d in &plan.dependencies {
+        let source = match &d.repo {
+            Some(repo) => format!("{} ({repo})", d.source.as_str()),
+            None => d.source.as_str().to_string(),
+        };
+        println!("  {} [{}]: {source}", d.dependency, d.kind.as_str());
+    }
+}
+
+/// Installs the dependencies from the sync databases with pacman and the ones from the AUR with yay
+fn install_plan(dir: &Path, plan: &InstallPlan) -> Result<(), PhaseError> {
+    let from_repos: Vec<&str> = plan
+        .dependencies
+        .iter()
+        .filter(|d| matches!(d.source, DependencySource::Repo | DependencySource::Local))
+        .map(|d| d.dependency.as_str())
+        .collect();
+    if !from_repos.is_empty() {
+        println!("INFO: installing {} dependencies from the repositories...", from_repos.len());
+        let installed = run_command(
+            Command::new("sudo")
+                .args(["pacman", "--noconfirm", "--needed", "--asdeps", "-S"])
+                .args(&from_repos),
+        );
+        if !installed {
+            return Err(PhaseError::with_item(104, &from_repos.join(" ")));
+        }
+    }
+
+    for dependency in plan.from_source(DependencySource::Aur) {
+        let name = dependency_name(&dependency.dependency);
+        println!("INFO: installing {name} from the AUR...");
+        let installed = run_command(
+            Command::new("yay")
+                .args(["--mflags", "--nocheck --ask 4"])
+                .args(["--noconfirm", "--removemake", "--needed", "--asdeps", "-S"])
+                .arg(name)
+                .current_dir(dir),
+        );
+        if !installed {
+            return Err(PhaseError::with_item(104, &dependency.dependency));
+        }
+    }
+    Ok(())
+}
+
+/// Returns whether the database of a repository can be fetched, so a missing one doesn't break `pacman -Sy`
+fn repo_available(name: &str, server: &str) -> bool {
+    let server = server
+        .replace("$repo", name)
+        .replace("$arch", env::consts::ARCH);
+    let db = format!("{}/{name}.db", server.trim_end_matches('/'));
+    match db.strip_prefix("file://") {
+        Some(path) => Path::new(path).exists(),
+        None => run_command(Command::new("curl").args(["-sfI", "-o", "/dev/null", &db])),
+    }
+}
+
+/// Appends `content` to a file only root may write to
+fn append_as_root(path: &Path, content: &str) -> Result<(), PhaseError> {
+    let item = path.to_string_lossy();
+    let mut child = Command::new("sudo")
+        .arg("tee")
+        .arg("-a")
+        .arg(path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .map_err(|_| PhaseError::with_item(103, &item))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(content.as_bytes())
+            .map_err(|_| PhaseError::with_item(103, &item))?;
+    }
+    match child.wait() {
+        Ok(status) if status.success() => Ok(()),
+        _ => Err(PhaseError::with_item(103, &item)),
+    }
+}
+
+/// Returns the directory of the local repository if it's mounted into the container
+fn local_repo_mount() -> Option<String> {
+    env::var("AB_LOCAL_REPO_SERVER")
+        .ok()
+        .and_then(|server| server.strip_prefix("file://").map(|path| path.to_string()))
+}
+
+/// Adds the repository of our own builds to `pacman_conf`, if the worker provides one
+fn configure_local_repo(pacman_conf: &Path) -> Result<Option<String>, PhaseError> {
+    let (Ok(name), Ok(server)) = (env::var("AB_LOCAL_REPO"), env::var("AB_LOCAL_REPO_SERVER")) else {
+        return Ok(None);
+    };
+    if !repo_available(&name, &server) {
+        println!("WARNING: local repository {name} is not available at {server}, skipping it");
+        return Ok(None);
+    }
+    let sig_level = env::var("AB_LOCAL_REPO_SIGLEVEL").unwrap_or("Optional TrustAll".to_string());
+    let section = format!("\n[{name}]\nSigLevel = {sig_level}\nServer = {server}\n");
+
+    println!("INFO: adding local repository {name} ({server})...");
+    append_as_root(pacman_conf, &section)?;
+    Ok(Some(name))
+}
+
+/// Returns the checksum of the synced database of a repository in the system at `root`,
+/// identifying the state it was used in
+fn repo_revision(root: &Path, name: &str) -> Option<String> {
+    let sync_dir = root.join(SYNC_DB_DIR.trim_start_matches('/'));
+    fs::read(sync_dir.join(format!("{name}.db")))
+        .ok()
+        .map(|db| sha256_hex(&db))
+}
+
+/// Installs the dependencies according to a freshly computed install plan, which is stored in `plan` even if the
+/// installation fails. Returns the revision of the local repository, if it was used.
+fn install_dependencies(
+    dir: &Path,
+    options: &str,
+    plan: &mut Option<InstallPlan>,
+) -> Result<Option<String>, PhaseError> {
+    let local_repo = configure_local_repo(Path::new(PACMAN_CONF))?;
+
+    println!("INFO: updating packages...");
+    let updated = run_command(
+        Command::new("yay")
+            .args(["--noconfirm", "--removemake", "-Syu"])
+            .current_dir(dir),
+    );
+    if !updated {
+        return Err(PhaseError::new(103));
+    }
+
+    let plan = plan.insert(plan_dependencies(dir, options, &local_repo, Path::new("/"))?);
+    print_plan(plan);
+    install_plan(dir, plan)?;
+    Ok(local_repo.and_then(|name| repo_revision(Path::new("/"), &name)))
+}
+
+/// Extracts the base chroot provided by the worker to `chroot_dir/root` and brings it up to date.
+///
+/// The chroot uses the `pacman.conf` and `makepkg.conf` of the container, so build profiles apply to it as well.
+/// `makechrootpkg` installs the dependencies itself, the plan stored in `plan` records how the updated chroot
+/// satisfies them. Fails if a dependency is only available from the AUR, which `makechrootpkg` can't install.
+/// Returns the revision of the local repository, if it was used.
+fn prepare_chroot(
+    archive: &str,
+    chroot_dir: &Path,
+    dir: &Path,
+    options: &str,
+    caches: &mut CacheUsage,
+    plan: &mut Option<InstallPlan>,
+) -> Result<Option<String>, PhaseError> {
+    let root = chroot_dir.join("root");
+    println!("INFO: extracting base chroot {archive}...");
+    let extracted = run_command(Command::new("sudo").arg("mkdir").arg("-p").arg(&root))
+        && run_command(
+            Command::new("sudo")
+                .args(["tar", "--numeric-owner", "-xzf", archive, "-C"])
+                .arg(&root),
+        );
+    if !extracted {
+        return Err(PhaseError::with_item(112, archive));
+    }
+    let root_log = root.join("var/log/pacman.log");
+    caches.watch_log(root_log.clone(), file_len(&root_log));
+
+    let copied = run_command(
+        Command::new("sudo")
+            .args(["cp", PACMAN_CONF])
+            .arg(root.join("etc/pacman.conf")),
+    );
+    if !copied {
+        return Err(PhaseError::with_item(112, PACMAN_CONF));
+    }
+    if let Some(home) = env::var_os("HOME")
+        && let Ok(makepkg_conf) = fs::read_to_string(Path::new(&home).join(".config/pacman/makepkg.conf"))
+    {
+        append_as_root(&root.join("etc/makepkg.conf"), &format!("\n{makepkg_conf}"))?;
+    }
+    let local_repo = configure_local_repo(&root.join("etc/pacman.conf"))?;
+
+    println!("INFO: updating the chroot...");
+    let mut update = Command::new("sudo");
+    update.arg("arch-nspawn").arg(&root);
+    if let Some(mount) = local_repo_mount() {
+        update.arg(format!("--bind-ro={mount}"));
+    }
+    if !run_command(update.args(["pacman", "--noconfirm", "-Syu"])) {
+        return Err(PhaseError::new(103));
+    }
+    // the working copy starts with the log of the updated chroot
+    caches.watch_log(
+        chroot_dir.join(CHROOT_COPY).join("var/log/pacman.log"),
+        file_len(&root_log),
+    );
+
+    let plan = plan.insert(plan_dependencies(dir, options, &local_repo, &root)?);
+    print_plan(plan);
+    if let Some(dependency) = plan.from_source(DependencySource::Aur).next() {
+        eprintln!("ERROR: makechrootpkg can't install dependencies from the AUR, like {}", dependency.dependency);
+        return Err(PhaseError::with_item(104, &dependency.dependency));
+    }
+    Ok(local_repo.and_then(|name| repo_revision(&root, &name)))
+}
+
+fn run_makepkg(dir: &Path, options: &str) -> Result<(), PhaseError> {
+    let built = run_command(
+        Command::new("makepkg")
+            .args(["-s", "-c", "-C", "--noconfirm", "--noprogressbar"])
+            .args(options.split_whitespace())
+            .envs(compiler_cache_env())
+            .envs(distcc_env())
+            .current_dir(dir),
+    );
+    if !built {
+        return Err(PhaseError::new(105));
+    }
+    Ok(())
+}
+
+/// Enables a `BUILDENV` option like `ccache` or `distcc` in the `makepkg.conf` of the user
+fn enable_buildenv(home: &Path, option: &str) -> Result<(), PhaseError> {
+    let config_dir = home.join(".config/pacman");
+    let appended = fs::create_dir_all(&config_dir).and_then(|_| {
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(config_dir.join("makepkg.conf"))?
+            .write_all(format!("\nBUILDENV+=({option})\n").as_bytes())
+    });
+    appended.map_err(|_| PhaseError::with_item(105, "makepkg.conf"))
+}
+
+/// Builds the package in a copy of the chroot, `makechrootpkg` installs the dependencies itself.
+///
+/// Runs as root, the sources are downloaded as `builder`.
+fn run_makechrootpkg(dir: &Path, chroot_dir: &Path, options: &str) -> Result<(), PhaseError> {
+    let mut command = Command::new("makechrootpkg");
+    command
+        .args(["-U", BUILDER_USER, "-c", "-l", CHROOT_COPY, "-r"])
+        .arg(chroot_dir);
+    if let Some(mount) = local_repo_mount() {
+        command.args(["-D", &mount]);
+    }
+    let built = run_command(
+        command
+            .args(["--", "--noconfirm", "--noprogressbar"])
+            .args(options.split_whitespace())
+            .current_dir(dir),
+    );
+    if !built {
+        return Err(PhaseError::new(105));
+    }
+    Ok(())
+}
+
+fn copy_results(dir: &Path) -> Result<Vec<ProducedFile>, PhaseError> {
+    let entries = fs::read_dir(dir).map_err(|_| PhaseError::new(106))?;
+    let mut copied = Vec::new();
+    for entry in entries.flatten() {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if !file_name.contains(".pkg.tar.") {
+            continue;
+        }
+        let target = Path::new(RESULTS_DIR).join(&file_name);
+        let size = fs::copy(entry.path(), &target).map_err(|_| PhaseError::with_item(106, &file_name))?;
+        let sha256 = file_sha256(&target).map_err(|_| PhaseError::with_item(106, &file_name))?;
+        copied.push(ProducedFile {
+            name: file_name,
+            size,
+            sha256,
+        });
+    }
+    if copied.is_empty() {
+        return Err(PhaseError::new(106));
+    }
+    Ok(copied)
+}
+
+/// Runs namcap on the PKGBUILD and every produced package.
+///
+/// Findings with a tag listed in `AB_NAMCAP_ALLOW` are dropped, namcap failing doesn't fail the build.
+fn run_namcap(dir: &Path, produced_files: &[ProducedFile]) -> Vec<LintFinding> {
+    let allowed: Vec<String> = env::var("AB_NAMCAP_ALLOW")
+        .unwrap_or_default()
+        .split([',', ' '])
+        .filter(|tag| !tag.is_empty())
+        .map(|tag| tag.to_string())
+        .collect();
+    let mut targets = vec![dir.join("PKGBUILD")];
+    targets.extend(produced_files.iter().map(|f| Path::new(RESULTS_DIR).join(&f.name)));
+
+    let mut findings = Vec::new();
+    let mut suppressed = 0;
+    for target in targets {
+        let output = match Command::new("namcap").arg("-m").arg(&target).current_dir(dir).output() {
+            Ok(output) => output,
+            Err(e) => {
+                eprintln!("WARNING: failed to run namcap: {e}");
+                return findings;
+            }
+        };
+        for finding in String::from_utf8_lossy(&output.stdout).lines().filter_map(parse_namcap_line) {
+            if allowed.contains(&finding.tag) {
+                suppressed += 1;
+                continue;
+            }
+            println!("namcap: {} {}: {} {}", finding.target, finding.severity.as_str(), finding.tag, finding.message);
+            findings.push(finding);
+        }
+    }
+    if suppressed > 0 {
+        println!("namcap: {suppressed} finding(s) suppressed by the allowlist");
+    }
+    findings
+}
+
+/// Returns whether the worker asked for a build in a clean chroot
+fn in_clean_chroot() -> bool {
+    env::var("AB_CHROOT_ARCHIVE").is_ok_and(|archive| !archive.is_empty())
+}
+
+fn file_len(path: &Path) -> u64 {
+    fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+fn run(report: &mut BuildReport, caches: &mut CacheUsage) -> Result<(), i32> {
+    let source = require_env("AB_SOURCE")?;
+    let options = env::var("AB_OPTIONS").unwrap_or_default();
+    let home = PathBuf::from(env::var("HOME").map_err(|_| 100)?);
+    drop_builder_sudo()?;
+
+    run_phase(report, Phase::Clone, || clone_source(&home, &source))?;
+
+    let mut dir = home.join("source");
+    if let Ok(subfolder) = env::var("AB_SUBFOLDER")
+        && !subfolder.is_empty()
+    {
+        dir = dir.join(subfolder);
+    }
+    if !dir.is_dir() {
+        return Err(100);
+    }
+    caches.package_dir = Some(dir.clone());
+
+    if let Some(files) = verify_files() {
+        let work_dir = home.join("verify");
+        report.reproducibility = run_phase(report, Phase::Verify, || {
+            let originals = fetch_originals(&files, &work_dir.join("original"))?;
+            rebuild_and_compare(&dir, &originals, &work_dir)
+        })?;
+        return Ok(());
+    }
+
+    match env::var("AB_CHROOT_ARCHIVE") {
+        Ok(archive) if in_clean_chroot() => {
+            let chroot_dir = home.join("chroot");
+            let mut plan = None;
+            let prepared = run_phase(report, Phase::Dependencies, || {
+                prepare_chroot(&archive, &chroot_dir, &dir, &options, caches, &mut plan)
+            });
+            report.install_plan = plan;
+            report.local_repo_revision = prepared?;
+            report.clean_chroot = true;
+            run_phase(report, Phase::Makepkg, || run_makechrootpkg(&dir, &chroot_dir, &options))?;
+        }
+        _ => {
+            let mut plan = None;
+            let installed = run_phase(report, Phase::Dependencies, || {
+                install_dependencies(&dir, &options, &mut plan)
+            });
+            report.install_plan = plan;
+            report.local_repo_revision = installed?;
+            run_phase(report, Phase::Makepkg, || {
+                if compiler_cache_dir("AB_CCACHE_DIR").is_some() {
+                    enable_buildenv(&home, "ccache")?;
+                }
+                if let Some(hosts) = distcc_hosts() {
+                    println!("INFO: distributing compile jobs to {hosts}");
+                    enable_buildenv(&home, "distcc")?;
+                }
+                run_makepkg(&dir, &options)
+            })?;
+        }
+    }
+    report.produced_files = run_phase(report, Phase::Copy, || copy_results(&dir))?;
+    report.lint_findings = run_namcap(&dir, &report.produced_files);
+
+    Ok(())
+}
+
+fn main() {
+    let mut report = BuildReport::default();
+    let mut caches = CacheUsage::start();
+    report.exit_code = run(&mut report, &mut caches).err().unwrap_or(0);
+    let srcinfo = caches.package_dir.as_deref().and_then(|dir| read_srcinfo(dir).ok());
+    report.cache_stats = caches.finish(srcinfo.as_ref());
+    report.distcc = distcc_stats();
+
+    match serde_json::to_string_pretty(&report) {
+        Ok(json) => {
+            if let Err(e) = fs::write(REPORT_PATH, json) {
+                eprintln!("ERROR: failed to write build report: {e}");
+            }
+        }
+        Err(e) => eprintln!("ERROR: failed to serialize build report: {e}"),
+    }
+
+    exit(report.exit_code);
+}
@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use srcinfo::{ArchVec, Srcinfo};
+
+/// The array of the PKGBUILD a dependency is listed in
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyKind {
+    Depends,
+    Makedepends,
+    Checkdepends,
+}
+
+impl DependencyKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DependencyKind::Depends => "depends",
+            DependencyKind::Makedepends => "makedepends",
+            DependencyKind::Checkdepends => "checkdepends",
+        }
+    }
+}
+
+/// Where a dependency is installed from
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencySource {
+    /// Already satisfied by the installed packages
+    Installed,
+    /// One of the configured pacman repositories
+    Repo,
+    /// The repository of our own builds
+    Local,
+    Aur,
+}
+
+impl DependencySource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DependencySource::Installed => "installed",
+            DependencySource::Repo => "repo",
+            DependencySource::Local => "local",
+            DependencySource::Aur => "aur",
+        }
+    }
+}
+
+/// A dependency of the package and how it's satisfied
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PlannedDependency {
+    /// The dependency as written in the PKGBUILD, including its version constraint
+    pub dependency: String,
+    pub kind: DependencyKind,
+    pub source: DependencySource,
+    /// Name of the repository it's installed from
+    pub repo: Option<String>,
+}
+
+/// Every dependency of a build, decided before anything is installed
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct InstallPlan {
+    pub dependencies: Vec<PlannedDependency>,
+}
+
+impl InstallPlan {
+    /// Returns the dependencies installed from the given source
+    pub fn from_source(&self, source: DependencySource) -> impl Iterator<Item = &PlannedDependency> {
+        self.dependencies.iter().filter(move |d| d.source == source)
+    }
+}
+
+/// Returns the name of a dependency without its version constraint.
+///
+/// # Example
+///
+/// ```
+/// use builder_agent::plan::dependency_name;
+/// assert_eq!(dependency_name("python>=3.12"), "python");
+/// assert_eq!(dependency_name("glibc<2.40"), "glibc");
+/// assert_eq!(dependency_name("libfoo.so=1-64"), "libfoo.so");
+/// ```
+pub fn dependency_name(dependency: &str) -> &str {
+    dependency
+        .split(['<', '>', '='])
+        .next()
+        .unwrap_or(dependency)
+}
+
+/// Returns all dependencies of the packages in `srcinfo` that apply to `arch`, without duplicates.
+///
+/// Dependencies on packages built from the same PKGBUILD are left out, `checkdepends` only if `check` is set.
+pub fn collect_dependencies(srcinfo: &Srcinfo, arch: &str, check: bool) -> Vec<(String, DependencyKind)> {
+    let mut arrays: Vec<(&[ArchVec], DependencyKind)> = srcinfo
+        .pkgs()
+        .iter()
+        .map(|p| (p.depends.as_slice(), DependencyKind::Depends))
+        .collect();
+    arrays.push((&srcinfo.base.makedepends, DependencyKind::Makedepends));
+    if check {
+        arrays.push((&srcinfo.base.checkdepends, DependencyKind::Checkdepends));
+    }
+
+    let mut dependencies: Vec<(String, DependencyKind)> = Vec::new();
+    for (array, kind) in arrays {
+        for dependency in ArchVec::active(array, arch) {
+            let own = srcinfo.names().any(|n| n == dependency_name(dependency));
+            if !own && !dependencies.iter().any(|(d, _)| d == dependency) {
+                dependencies.push((dependency.to_string(), kind));
+            }
+        }
+    }
+    dependencies
+}
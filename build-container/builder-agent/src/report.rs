@@ -0,0 +1,141 @@
+use crate::lint::LintFinding;
+use crate::plan::InstallPlan;
+use crate::repro::ReproducedFile;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::Path;
+
+/// Path of the report inside the build container
+pub const REPORT_PATH: &str = "/results/report.json";
+
+/// The steps of a build, in the order they are run.
+///
+/// All phases except `Test` and `Upload` run inside the build container, those are done by the worker.
+/// Reproducibility checks run `Verify` instead of `Dependencies` and `Makepkg`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    Clone,
+    Dependencies,
+    Makepkg,
+    /// Rebuild of published packages to check that they are reproducible
+    Verify,
+    Copy,
+    /// Smoke test of the built packages in a fresh container
+    Test,
+    Upload,
+}
+
+impl Phase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Phase::Clone => "clone",
+            Phase::Dependencies => "dependencies",
+            Phase::Makepkg => "makepkg",
+            Phase::Verify => "verify",
+            Phase::Copy => "copy",
+            Phase::Test => "test",
+            Phase::Upload => "upload",
+        }
+    }
+}
+
+/// The outcome of a single build phase
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PhaseReport {
+    pub phase: Phase,
+    /// Start of the phase in milliseconds since the Unix epoch
+    pub started_at: i64,
+    /// End of the phase in milliseconds since the Unix epoch
+    pub finished_at: i64,
+    /// `0` on success, otherwise one of the build error codes
+    pub exit_status: i32,
+    /// The dependency, file, ... the phase failed on
+    pub failing_item: Option<String>,
+}
+
+impl PhaseReport {
+    pub fn duration_ms(&self) -> i64 {
+        self.finished_at - self.started_at
+    }
+}
+
+/// A package file produced by the build
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProducedFile {
+    pub name: String,
+    pub size: u64,
+    /// Hex encoded SHA-256 checksum of the file
+    pub sha256: String,
+}
+
+/// How often a cache provided by the worker was used during a build
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CacheStats {
+    /// `sources`, `packages`, `ccache` or `sccache`
+    pub cache: String,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// Where the compile jobs of a build distributed with distcc ran
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DistccStats {
+    /// Jobs compiled by the helpers of other workers
+    pub remote: u64,
+    /// Jobs compiled in the build container, including the ones that failed to distribute
+    pub local: u64,
+}
+
+/// Written by the builder agent at the end of every build
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct BuildReport {
+    pub phases: Vec<PhaseReport>,
+    pub produced_files: Vec<ProducedFile>,
+    pub exit_code: i32,
+    /// SHA-256 checksum of the local repository database the dependencies were installed from
+    pub local_repo_revision: Option<String>,
+    /// Whether the package was built in a fresh chroot instead of the container itself
+    #[serde(default)]
+    pub clean_chroot: bool,
+    /// How the dependencies were installed, missing if the build didn't get that far
+    #[serde(default)]
+    pub install_plan: Option<InstallPlan>,
+    /// Hits and misses of the caches mounted by the worker
+    #[serde(default)]
+    pub cache_stats: Vec<CacheStats>,
+    /// Set if the compile jobs were distributed with distcc
+    #[serde(default)]
+    pub distcc: Option<DistccStats>,
+    /// namcap findings for the PKGBUILD and the produced packages, without the allowed tags
+    #[serde(default)]
+    pub lint_findings: Vec<LintFinding>,
+    /// Set for reproducibility checks, the rebuilt packages compared with the published ones
+    #[serde(default)]
+    pub reproducibility: Vec<ReproducedFile>,
+}
+
+impl BuildReport {
+    /// Returns the phase the build failed in, if any
+    pub fn failed_phase(&self) -> Option<&PhaseReport> {
+        self.phases.iter().find(|p| p.exit_status != 0)
+    }
+}
+
+/// Returns the hex encoded SHA-256 checksum of `data`
+pub fn sha256_hex(data: &[u8]) -> String {
+    to_hex(&Sha256::digest(data))
+}
+
+/// Returns the hex encoded SHA-256 checksum of a file without loading it into memory
+pub fn file_sha256(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut BufReader::new(File::open(path)?), &mut hasher)?;
+    Ok(to_hex(&hasher.finalize()))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
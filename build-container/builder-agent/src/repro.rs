@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// How a file inside a rebuilt package differs from the original package
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FileChange {
+    /// Only in the rebuilt package
+    Added,
+    /// Only in the original package
+    Removed,
+    Changed,
+}
+
+impl FileChange {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FileChange::Added => "added",
+            FileChange::Removed => "removed",
+            FileChange::Changed => "changed",
+        }
+    }
+}
+
+/// A file that differs between the original and the rebuilt package.
+///
+/// An empty path means the contents are equal and only the archive itself differs, e.g. in compression.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FileDifference {
+    pub path: String,
+    pub change: FileChange,
+}
+
+/// The outcome of rebuilding a published package
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReproducedFile {
+    pub name: String,
+    pub original_sha256: String,
+    /// Missing if the rebuild didn't produce this package
+    pub rebuilt_sha256: Option<String>,
+    pub differences: Vec<FileDifference>,
+}
+
+impl ReproducedFile {
+    /// Returns whether the rebuilt package is identical to the original byte for byte
+    pub fn reproducible(&self) -> bool {
+        self.rebuilt_sha256.as_ref() == Some(&self.original_sha256)
+    }
+}
+
+/// Compares the contents of two packages, given as paths mapped to a checksum of each entry.
+///
+/// # Example
+///
+/// ```
+/// use builder_agent::repro::{FileChange, diff_contents};
+/// use std::collections::BTreeMap;
+/// let original = BTreeMap::from([
+///     ("usr/bin/foo".to_string(), "aa".to_string()),
+///     (".BUILDINFO".to_string(), "bb".to_string()),
+/// ]);
+/// let rebuilt = BTreeMap::from([
+///     ("usr/bin/foo".to_string(), "ac".to_string()),
+///     ("usr/share/foo.txt".to_string(), "cc".to_string()),
+/// ]);
+/// let differences = diff_contents(&original, &rebuilt);
+/// assert_eq!(differences.len(), 3);
+/// assert_eq!(differences[0].path, ".BUILDINFO");
+/// assert_eq!(differences[0].change, FileChange::Removed);
+/// assert_eq!(differences[1].change, FileChange::Changed);
+/// assert_eq!(differences[2].change, FileChange::Added);
+/// ```
+pub fn diff_contents(
+    original: &BTreeMap<String, String>,
+    rebuilt: &BTreeMap<String, String>,
+) -> Vec<FileDifference> {
+    let mut paths: Vec<&String> = original.keys().chain(rebuilt.keys()).collect();
+    paths.sort();
+    paths.dedup();
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let change = match (original.get(path), rebuilt.get(path)) {
+                (Some(a), Some(b)) if a == b => return None,
+                (Some(_), Some(_)) => FileChange::Changed,
+                (Some(_), None) => FileChange::Removed,
+                (None, _) => FileChange::Added,
+            };
+            Some(FileDifference {
+                path: path.clone(),
+                change,
+            })
+        })
+        .collect()
+}
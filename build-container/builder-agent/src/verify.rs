@@ -0,0 +1,163 @@
+use builder_agent::report::file_sha256;
+use builder_agent::repro::{FileChange, FileDifference, ReproducedFile, diff_contents};
+use crate::{BUILDER_USER, PhaseError, run_command};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Where `makerepropkg` keeps its chroots and the packages it rebuilt
+const REPRO_BUILDROOT: &str = "/var/lib/archbuild/reproducible";
+
+/// Returns the original packages to rebuild, set by the worker for a reproducibility check
+pub fn verify_files() -> Option<Vec<String>> {
+    env::var("AB_VERIFY_FILES")
+        .ok()
+        .map(|files| {
+            files
+                .split(',')
+                .filter(|f| !f.is_empty())
+                .map(|f| f.to_string())
+                .collect::<Vec<String>>()
+        })
+        .filter(|files| !files.is_empty())
+}
+
+/// Downloads the published packages from the local repository into `target`
+pub fn fetch_originals(files: &[String], target: &Path) -> Result<Vec<PathBuf>, PhaseError> {
+    let (Ok(name), Ok(server)) = (env::var("AB_LOCAL_REPO"), env::var("AB_LOCAL_REPO_SERVER")) else {
+        eprintln!("ERROR: the published packages are fetched from the local repository, but none is configured");
+        return Err(PhaseError::new(114));
+    };
+    let server = server
+        .replace("$repo", &name)
+        .replace("$arch", env::consts::ARCH);
+    fs::create_dir_all(target).map_err(|_| PhaseError::new(114))?;
+
+    let mut originals = Vec::new();
+    for file in files {
+        let path = target.join(file);
+        let url = format!("{}/{file}", server.trim_end_matches('/'));
+        let fetched = match url.strip_prefix("file://") {
+            Some(source) => fs::copy(source, &path).is_ok(),
+            None => run_command(Command::new("curl").args(["-fsSL", "-o"]).arg(&path).arg(&url)),
+        };
+        if !fetched {
+            return Err(PhaseError::with_item(114, file));
+        }
+        originals.push(path);
+    }
+    Ok(originals)
+}
+
+/// Returns the path of a package rebuilt by `makerepropkg`
+fn find_rebuilt(name: &str) -> Option<PathBuf> {
+    let output = Command::new("sudo")
+        .args(["find", REPRO_BUILDROOT, "-type", "f", "-name", name, "-print", "-quit"])
+        .output()
+        .ok()?;
+    let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!path.is_empty()).then(|| PathBuf::from(path))
+}
+
+/// Maps every entry of an extracted package to the checksum of its content or its link target
+fn collect_contents(root: &Path, dir: &Path, contents: &mut BTreeMap<String, String>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = fs::symlink_metadata(&path) else {
+            continue;
+        };
+        let relative = path.strip_prefix(root).unwrap_or(&path).to_string_lossy().to_string();
+        if metadata.is_dir() {
+            collect_contents(root, &path, contents);
+        } else if metadata.is_symlink() {
+            let target = fs::read_link(&path).unwrap_or_default();
+            contents.insert(relative, format!("-> {}", target.to_string_lossy()));
+        } else {
+            let checksum = file_sha256(&path).unwrap_or_default();
+            contents.insert(relative, checksum);
+        }
+    }
+}
+
+/// Extracts a package into `target` and returns its contents
+fn package_contents(package: &Path, target: &Path) -> BTreeMap<String, String> {
+    let mut contents = BTreeMap::new();
+    if fs::create_dir_all(target).is_err()
+        || !run_command(Command::new("bsdtar").arg("-xf").arg(package).arg("-C").arg(target))
+    {
+        eprintln!("WARNING: failed to extract {}", package.display());
+        return contents;
+    }
+    collect_contents(target, target, &mut contents);
+    contents
+}
+
+/// Rebuilds the packages with `makerepropkg`, which recreates the environment recorded in their `.BUILDINFO`,
+/// and compares the results with the originals.
+///
+/// Runs as root like the clean chroot builds, the package code only runs in the chroot and as `builder`.
+pub fn rebuild_and_compare(dir: &Path, originals: &[PathBuf], work_dir: &Path) -> Result<Vec<ReproducedFile>, PhaseError> {
+    // fails for unreproducible packages too, so the outcome is decided by the rebuilt files
+    run_command(
+        Command::new("makerepropkg")
+            .env("SUDO_USER", BUILDER_USER)
+            .args(["-l", "verify"])
+            .args(originals)
+            .current_dir(dir),
+    );
+
+    let mut reproduced = Vec::new();
+    for original in originals {
+        let name = original.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let original_sha256 = file_sha256(original).map_err(|_| PhaseError::with_item(105, &name))?;
+        let mut file = ReproducedFile {
+            name: name.clone(),
+            original_sha256,
+            rebuilt_sha256: None,
+            differences: Vec::new(),
+        };
+
+        if let Some(found) = find_rebuilt(&name) {
+            let rebuilt = work_dir.join("rebuilt").join(&name);
+            let copied = fs::create_dir_all(work_dir.join("rebuilt")).is_ok()
+                && run_command(Command::new("sudo").arg("cp").arg(&found).arg(&rebuilt))
+                && run_command(Command::new("sudo").args(["chmod", "644"]).arg(&rebuilt));
+            if copied && let Ok(rebuilt_sha256) = file_sha256(&rebuilt) {
+                file.rebuilt_sha256 = Some(rebuilt_sha256);
+                if !file.reproducible() {
+                    let original_tree = package_contents(original, &work_dir.join("contents/original").join(&name));
+                    let rebuilt_tree = package_contents(&rebuilt, &work_dir.join("contents/rebuilt").join(&name));
+                    file.differences = diff_contents(&original_tree, &rebuilt_tree);
+                    if file.differences.is_empty() {
+                        file.differences.push(FileDifference {
+                            path: String::new(),
+                            change: FileChange::Changed,
+                        });
+                    }
+                }
+            }
+        }
+
+        match (&file.rebuilt_sha256, file.reproducible()) {
+            (None, _) => println!("WARNING: {name} was not rebuilt"),
+            (Some(_), true) => println!("INFO: {name} is reproducible"),
+            (Some(_), false) => {
+                println!("WARNING: {name} is not reproducible, {} difference(s):", file.differences.len());
+                for difference in &file.differences {
+                    println!("  {} {}", difference.change.as_str(), difference.path);
+                }
+            }
+        }
+        reproduced.push(file);
+    }
+
+    if reproduced.iter().all(|f| f.rebuilt_sha256.is_none()) {
+        return Err(PhaseError::new(105));
+    }
+    Ok(reproduced)
+}
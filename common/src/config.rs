@@ -1,5 +1,6 @@
-use crate::types::{AurPackageSettings, GitPackageSettings};
+use crate::types::{AurPackageSettings, GitPackageSettings, ResourceLimits};
 use config;
+use std::collections::HashMap;
 use config::{Config, ConfigError};
 use serde::{Deserialize, Serialize};
 use serde::de::DeserializeOwned;
@@ -27,7 +28,11 @@ pub trait Configurable: DeserializeOwned {
 pub struct ServerConfig {
     pub aur_packages: Vec<AurPackageSettings>,
     pub git_packages: Vec<GitPackageSettings>,
-    pub sleepduration: Option<u64>
+    pub sleepduration: Option<u64>,
+    /// Seconds without a heartbeat after which a worker is considered offline
+    pub worker_timeout: Option<u64>,
+    /// Seconds after a build before its packages are rebuilt to check that they are reproducible, defaults to a day
+    pub reproducibility_delay: Option<u64>,
 }
 
 impl Configurable for ServerConfig {}
@@ -49,6 +54,14 @@ pub struct NotifierConfig {
 
 impl Configurable for NotifierConfig {}
 
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct WebConfig {
+    /// Root directory of the pacman repositories, laid out as `<root>/<name>/<arch>/`
+    pub repositories: Option<String>,
+}
+
+impl Configurable for WebConfig {}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct GiteaSettings {
     pub repo: String,
@@ -56,12 +69,186 @@ pub struct GiteaSettings {
     pub token: String,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DirectorySettings {
+    /// Directory of the pacman repository
+    pub path: String,
+    /// Name of the repository database to add the packages to, none to only copy the files
+    pub repo: Option<String>,
+    /// Number of old package versions kept in the directory
+    pub keep_versions: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct S3Settings {
+    /// Base URL of the S3 API, e.g. `http://minio:9000`
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: Option<String>,
+    pub access_key: String,
+    pub secret_key: String,
+    /// Prefix prepended to the object keys
+    pub prefix: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HttpSettings {
+    /// Base URL, the file name is appended to it
+    pub url: String,
+    pub user: Option<String>,
+    pub password: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SigningSettings {
+    /// Armored or binary OpenPGP secret key used to sign packages and repository databases
+    pub key_file: String,
+    pub passphrase: Option<String>,
+}
+
+/// Repository of our own builds, made available to the build containers as a dependency source
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LocalRepoSettings {
+    /// Name of the repository database
+    pub name: String,
+    /// Directory of the repository on the docker host, mounted into the build containers
+    pub path: Option<String>,
+    /// URL the repository is served at if no path is set, e.g. `http://web:3000/repo/$repo/$arch`
+    pub url: Option<String>,
+    /// `SigLevel` of the repository in `pacman.conf`, defaults to `Optional TrustAll`
+    pub sig_level: Option<String>,
+}
+
+/// Cache of the base chroot that clean builds start from
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChrootSettings {
+    /// Directory on the docker host the base chroot archive is kept in
+    pub path: String,
+    /// Hours after which the base chroot is recreated, defaults to 24
+    pub refresh_interval: Option<u64>,
+}
+
+/// Caches shared by all builds of a worker
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CacheSettings {
+    /// Directory of the caches, must be the same path on the docker host and for the worker
+    pub path: String,
+    /// Size limit of the source and package caches together in MiB, the least recently used entries are removed above it
+    pub max_size: Option<u64>,
+    /// Enables ccache for builds outside of a clean chroot
+    pub ccache: Option<bool>,
+    /// Size limit of the ccache directory in MiB, enforced by ccache
+    pub ccache_max_size: Option<u64>,
+    /// Enables sccache for Rust builds outside of a clean chroot
+    pub sccache: Option<bool>,
+    /// Size limit of the sccache directory in MiB, enforced by sccache
+    pub sccache_max_size: Option<u64>,
+}
+
+/// distcc daemon the worker runs for the builds of other workers
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DistccSettings {
+    /// Host name or IP the other workers reach this one at
+    pub address: String,
+    /// Port the daemon is published on, defaults to 3632
+    pub port: Option<u16>,
+    /// Number of compile jobs accepted at the same time, defaults to the number of CPUs
+    pub jobs: Option<u32>,
+    /// Networks allowed to connect, defaults to the private IPv4 ranges
+    pub allow: Option<Vec<String>>,
+}
+
+/// Fresh containers the built packages are installed into for their smoke tests, with the repositories of their profile
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SmokeTestSettings {
+    /// Image of the test containers, defaults to `archlinux:latest`
+    pub image: Option<String>,
+    /// Maximum duration of a test in seconds, including the installation, defaults to 600
+    pub timeout: Option<u64>,
+}
+
+/// A destination built packages are published to
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PublisherSettings {
+    Gitea(GiteaSettings),
+    Directory(DirectorySettings),
+    S3(S3Settings),
+    /// Generic HTTP `PUT` target, e.g. a WebDAV share
+    Http(HttpSettings),
+}
+
+/// An additional repository in the `pacman.conf` of the build container
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PacmanRepository {
+    pub name: String,
+    pub servers: Option<Vec<String>>,
+    /// File with the servers, e.g. `/etc/pacman.d/mirrorlist`
+    pub include: Option<String>,
+    pub sig_level: Option<String>,
+}
+
+/// A named set of container settings that packages can build with
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct BuildProfile {
+    pub resources: Option<ResourceLimits>,
+    /// Value of `MAKEFLAGS` inside the build container
+    pub makeflags: Option<String>,
+    /// Repositories added to `pacman.conf` after `core` and `extra`
+    pub repositories: Option<Vec<PacmanRepository>>,
+    /// Default `SigLevel` in `pacman.conf`
+    pub sig_level: Option<String>,
+    pub cflags: Option<String>,
+    pub cxxflags: Option<String>,
+    pub ldflags: Option<String>,
+    pub rustflags: Option<String>,
+    /// Compression of the built packages, e.g. `.pkg.tar.zst`
+    pub pkgext: Option<String>,
+    /// Replaces the `OPTIONS` array of `makepkg.conf`, e.g. `["!debug", "lto"]`
+    pub options: Option<Vec<String>>,
+    /// Build in a fresh chroot created from the worker's base chroot instead of the container itself
+    pub clean_chroot: Option<bool>,
+}
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct WorkerConfig {
     pub builder:  Option<String>,
     pub builder_tag: Option<String>,
-    pub gitea: GiteaSettings,
+    /// Gitea package registry, available as the publisher `gitea`
+    pub gitea: Option<GiteaSettings>,
+    /// Named publish destinations
+    pub publishers: Option<HashMap<String, PublisherSettings>>,
+    /// Signs packages and repository databases if set
+    pub signing: Option<SigningSettings>,
+    /// Injected into the `pacman.conf` of the build containers
+    pub local_repo: Option<LocalRepoSettings>,
+    /// Base chroot for builds with profiles requiring a clean chroot
+    pub chroot: Option<ChrootSettings>,
+    /// Source, package and compiler caches mounted into the build containers
+    pub cache: Option<CacheSettings>,
+    /// Offers a distcc helper to the builds of all workers
+    pub distcc: Option<DistccSettings>,
+    /// Containers the smoke tests of packages run in
+    pub smoke_test: Option<SmokeTestSettings>,
+    /// Default maximum build duration in seconds
+    pub build_timeout: Option<u64>,
+    /// Number of builds that may run at the same time
+    pub concurrent_builds: Option<usize>,
+    /// Number of unacknowledged tasks fetched from the queue, defaults to `concurrent_builds`
+    pub prefetch: Option<u16>,
+    /// Default resource limits of a single build slot
+    pub slot_resources: Option<ResourceLimits>,
+    pub profiles: Option<HashMap<String, BuildProfile>>,
+    /// Capabilities of this worker, packages requiring labels are only built by workers having all of them.
+    /// At most [`MAX_WORKER_LABELS`](crate::routing::MAX_WORKER_LABELS), the worker consumes a queue per subset.
+    pub labels: Option<Vec<String>>,
+    /// Stable identifier of this worker, generated and stored in `worker_id_path` if not set
+    pub worker_id: Option<String>,
+    /// File the generated worker id is kept in, defaults to `/var/lib/aur-builder/worker_id`.
+    /// Replicas sharing it use `worker_id.1`, `worker_id.2`, ... next to it.
+    pub worker_id_path: Option<String>,
+    /// Seconds between two heartbeats
+    pub heartbeat_interval: Option<u64>,
 }
 
 impl Configurable for WorkerConfig {}
\ No newline at end of file
@@ -7,9 +7,27 @@ use serde_json::Value;
 
 const BUILD_ERROR_CODES_JSON : &str = include_str!("./error_codes.json");
 
+/// Status code reported by the worker when a build exceeds its timeout
+pub const BUILD_TIMEOUT_CODE: i64 = 108;
+
+/// Status code reported by the worker when it fails to upload a package
+pub const UPLOAD_FAILED_CODE: i64 = 107;
+
+/// Status code reported by the worker when the result files can't be copied out of the build container
+pub const EXTRACTION_FAILED_CODE: i64 = 109;
+
+/// Status code reported by the worker when an extracted file doesn't match the checksum in the build report
+pub const CHECKSUM_MISMATCH_CODE: i64 = 110;
+
+/// Status code reported by the worker when it fails to sign a package
+pub const SIGNING_FAILED_CODE: i64 = 111;
+
+/// Status code reported by the worker when the built packages fail their smoke test
+pub const SMOKE_TEST_FAILED_CODE: i64 = 113;
+
 fn create_error_map() -> HashMap<i64, String> {
     let mut error_map = HashMap::new();
-    let pjson: Value = serde_json::from_str(&BUILD_ERROR_CODES_JSON).unwrap();
+    let pjson: Value = serde_json::from_str(BUILD_ERROR_CODES_JSON).unwrap();
     if let Value::Object(map) = pjson {
         for (key, value) in &map {
             let code = key.parse::<i64>().unwrap_or(-1);
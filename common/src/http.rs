@@ -0,0 +1,67 @@
+//! Helpers for serving files over HTTP.
+
+/// A single byte range of a `Range` header, both ends inclusive
+#[derive(Debug, PartialEq)]
+pub enum ByteRange {
+    Satisfiable(u64, u64),
+    Unsatisfiable,
+}
+
+/// Parses a `Range` header for a file of `size` bytes.
+///
+/// Returns `None` if the header should be ignored and the whole file sent, which includes multiple ranges.
+///
+/// # Example
+///
+/// ```
+/// use common::http::{ByteRange, parse_range};
+/// // closed ranges, the end is limited to the file
+/// assert_eq!(parse_range("bytes=0-99", 1000), Some(ByteRange::Satisfiable(0, 99)));
+/// assert_eq!(parse_range("bytes=900-2000", 1000), Some(ByteRange::Satisfiable(900, 999)));
+///
+/// // open ranges run to the end of the file
+/// assert_eq!(parse_range("bytes=500-", 1000), Some(ByteRange::Satisfiable(500, 999)));
+///
+/// // suffix ranges are the last bytes of the file
+/// assert_eq!(parse_range("bytes=-100", 1000), Some(ByteRange::Satisfiable(900, 999)));
+/// assert_eq!(parse_range("bytes=-5000", 1000), Some(ByteRange::Satisfiable(0, 999)));
+///
+/// // ranges outside of the file can't be satisfied
+/// assert_eq!(parse_range("bytes=1000-", 1000), Some(ByteRange::Unsatisfiable));
+/// assert_eq!(parse_range("bytes=-0", 1000), Some(ByteRange::Unsatisfiable));
+/// assert_eq!(parse_range("bytes=0-", 0), Some(ByteRange::Unsatisfiable));
+///
+/// // multiple ranges and invalid headers are ignored
+/// assert_eq!(parse_range("bytes=0-99,200-299", 1000), None);
+/// assert_eq!(parse_range("bytes=99-0", 1000), None);
+/// assert_eq!(parse_range("items=0-99", 1000), None);
+/// ```
+pub fn parse_range(header: &str, size: u64) -> Option<ByteRange> {
+    let spec = header.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') {
+        return None;
+    }
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix: u64 = suffix.parse().ok()?;
+            if suffix == 0 {
+                return Some(ByteRange::Unsatisfiable);
+            }
+            (size.saturating_sub(suffix), size.saturating_sub(1))
+        }
+        (start, "") => (start.parse().ok()?, size.saturating_sub(1)),
+        (start, end) => {
+            let start: u64 = start.parse().ok()?;
+            let end: u64 = end.parse().ok()?;
+            if end < start {
+                return None;
+            }
+            (start, end.min(size.saturating_sub(1)))
+        }
+    };
+    if size == 0 || start >= size {
+        return Some(ByteRange::Unsatisfiable);
+    }
+    Some(ByteRange::Satisfiable(start, end))
+}
@@ -10,6 +10,9 @@ use tokio::time::sleep;
 pub mod environment;
 pub mod errors;
 pub mod config;
+pub mod http;
+pub mod routing;
+pub mod sigv4;
 
 /// The maximum number of retry attempts for establishing a database/rabbitmq connection.
 pub const CONNECTION_RETRY_NUMBER: u8 = 10;
@@ -17,9 +20,14 @@ pub const CONNECTION_RETRY_NUMBER: u8 = 10;
 /// The timeout duration (in seconds) between retry attempts for connections.
 pub const RETRY_TIMEOUT: u8 = 10;
 
+/// Returns the address of the RabbitMQ server from `AMQP_ADDR`
+pub fn amqp_address() -> String {
+    std::env::var("AMQP_ADDR").unwrap_or_else(|_| "amqp://127.0.0.1:5672/%2f".into())
+}
+
 pub async fn connect_to_rabbitmq() -> Connection {
     info!("Connecting to rabbitmq...");
-    let q_addr = std::env::var("AMQP_ADDR").unwrap_or_else(|_| "amqp://127.0.0.1:5672/%2f".into());
+    let q_addr = amqp_address();
     let conn;
     let mut rabbit_retries: u8 = 0;
     loop {
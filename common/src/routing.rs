@@ -0,0 +1,131 @@
+use lapin::options::{ExchangeDeclareOptions, QueueBindOptions, QueueDeclareOptions};
+use lapin::types::{AMQPValue, FieldTable};
+use lapin::{Channel, ExchangeKind};
+
+/// The exchange build tasks are published to
+pub const BUILD_EXCHANGE: &str = "pkg_build";
+
+/// The queue for tasks that don't require any worker labels
+pub const DEFAULT_BUILD_QUEUE: &str = "pkg_build";
+
+/// Returns the routing key (and queue name) for tasks requiring the given labels.
+///
+/// The labels are sorted, so the order in the config doesn't matter.
+///
+/// # Example
+///
+/// ```
+/// use common::routing::build_routing_key;
+/// let key = build_routing_key(&["x86_64_v3".to_string(), "large".to_string()]);
+/// assert_eq!(key, "pkg_build.large.x86_64_v3");
+/// ```
+pub fn build_routing_key(labels: &[String]) -> String {
+    if labels.is_empty() {
+        return DEFAULT_BUILD_QUEUE.to_string();
+    }
+    let mut labels = labels.to_vec();
+    labels.sort();
+    labels.dedup();
+    format!("{DEFAULT_BUILD_QUEUE}.{}", labels.join("."))
+}
+
+/// Most labels a worker may have, it consumes one queue per subset of them
+pub const MAX_WORKER_LABELS: usize = 8;
+
+/// Returns the routing keys of all tasks a worker with the given labels is able to build.
+///
+/// These are the keys of every subset of `labels`, including the empty one. Tasks are routed by their required
+/// labels alone, so they wait for any suitable worker, even if none is online when they are published, and are
+/// shared by all workers able to build them.
+///
+/// # Example
+///
+/// ```
+/// use common::routing::{build_routing_key, worker_routing_keys};
+/// let labels = |l: &[&str]| l.iter().map(|l| l.to_string()).collect::<Vec<String>>();
+/// let keys = worker_routing_keys(&labels(&["x86_64_v3", "large"])).unwrap();
+/// assert_eq!(keys, vec!["pkg_build", "pkg_build.large", "pkg_build.x86_64_v3", "pkg_build.large.x86_64_v3"]);
+///
+/// // a task for which no worker is online is consumed by the first suitable worker that starts
+/// let task = build_routing_key(&labels(&["large"]));
+/// assert!(!worker_routing_keys(&labels(&["gpu"])).unwrap().contains(&task));
+/// assert!(worker_routing_keys(&labels(&["gpu", "large"])).unwrap().contains(&task));
+///
+/// assert!(worker_routing_keys(&labels(&["a", "b", "c", "d", "e", "f", "g", "h", "i"])).is_err());
+/// ```
+pub fn worker_routing_keys(labels: &[String]) -> Result<Vec<String>, String> {
+    let mut labels = labels.to_vec();
+    labels.sort();
+    labels.dedup();
+    if labels.len() > MAX_WORKER_LABELS {
+        return Err(format!(
+            "a worker may have at most {MAX_WORKER_LABELS} labels, it has {}",
+            labels.len()
+        ));
+    }
+    let mut keys = Vec::new();
+    for mask in 0..(1u32 << labels.len()) {
+        let subset: Vec<String> = labels
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| mask & (1 << i) != 0)
+            .map(|(_, l)| l.clone())
+            .collect();
+        keys.push(build_routing_key(&subset));
+    }
+    Ok(keys)
+}
+
+/// Declares the build exchange as well as the queue for `routing_key` and binds them.
+pub async fn declare_build_queue(channel: &Channel, routing_key: &str) -> lapin::Result<()> {
+    channel
+        .exchange_declare(
+            BUILD_EXCHANGE,
+            ExchangeKind::Direct,
+            ExchangeDeclareOptions::default(),
+            FieldTable::default(),
+        )
+        .await?;
+    channel
+        .queue_declare(
+            routing_key,
+            QueueDeclareOptions::default(),
+            FieldTable::default(),
+        )
+        .await?;
+    channel
+        .queue_bind(
+            routing_key,
+            BUILD_EXCHANGE,
+            routing_key,
+            QueueBindOptions::default(),
+            FieldTable::default(),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Returns the name of the stream queue carrying the live log of a build
+pub fn log_stream_name(build_id: &str) -> String {
+    format!("build_log.{build_id}")
+}
+
+/// Declares the stream queue for the live log of a build.
+///
+/// Streams keep their messages after they are consumed, so late subscribers can read the log from the start.
+pub async fn declare_log_stream(channel: &Channel, build_id: &str) -> lapin::Result<()> {
+    let mut arguments = FieldTable::default();
+    arguments.insert("x-queue-type".into(), AMQPValue::LongString("stream".into()));
+    arguments.insert("x-max-age".into(), AMQPValue::LongString("1D".into()));
+    channel
+        .queue_declare(
+            &log_stream_name(build_id),
+            QueueDeclareOptions {
+                durable: true,
+                ..QueueDeclareOptions::default()
+            },
+            arguments,
+        )
+        .await?;
+    Ok(())
+}
@@ -1,7 +1,8 @@
 use sea_orm::sqlx::types::chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
+pub use builder_agent::report::{BuildReport, Phase, PhaseReport, ProducedFile};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PackageSearchResult {
     pub name: String,
     pub version: String,
@@ -10,16 +11,108 @@ pub struct PackageSearchResult {
     pub source: Option<String>,
     pub subfolder: Option<String>,
     pub options: Option<String>,
-    pub environment: Option<Environment>
+    pub environment: Option<Environment>,
+    pub timeout: Option<u64>,
+    pub resources: Option<ResourceLimits>,
+    pub profile: Option<String>,
+    pub labels: Option<Vec<String>>,
+    pub publish_to: Option<Vec<String>>,
+    pub variant: Option<String>,
+    pub cache: Option<bool>,
+    pub distcc: Option<bool>,
+    pub smoke_test: Option<String>,
+    pub namcap_allow: Option<Vec<String>>,
+    pub verify_reproducible: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct BuildResultTransmissionFormat {
     pub task: BuildTaskTransmissionFormat,
+    /// Empty in results of workers that predate live logs
+    #[serde(default)]
+    pub build_id: String,
     pub status_code: i64,
     pub log_lines: Vec<String>,
     pub success: bool,
-    pub timestamps: Timestamps
+    pub timestamps: Timestamps,
+    /// Report written by the builder agent, missing if the build didn't get to write one
+    pub report: Option<BuildReport>,
+    #[serde(default)]
+    pub uploads: Vec<ArtifactUpload>,
+    /// Fingerprint of the key the packages were signed with
+    pub signing_key: Option<String>,
+    /// Build profile applied by the worker
+    pub profile: Option<String>,
+    /// Set if the package has a smoke test and the build succeeded
+    pub smoke_test: Option<SmokeTestResult>,
+    /// Metadata of the built package files, empty if the build failed
+    #[serde(default)]
+    pub packages: Vec<PackageInfo>,
+}
+
+/// The outcome of installing the built packages into a fresh container and running the smoke test command
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SmokeTestResult {
+    pub command: String,
+    pub exit_code: i64,
+    pub success: bool,
+    /// Output of the installation and the command, kept apart from the build log
+    pub log_lines: Vec<String>,
+}
+
+/// Metadata of a built package file, read from its `.PKGINFO`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PackageInfo {
+    pub file: String,
+    pub name: String,
+    pub version: String,
+    pub arch: String,
+    pub description: Option<String>,
+    pub url: Option<String>,
+    /// Installed size in bytes
+    pub size: i64,
+    pub licenses: Vec<String>,
+    pub depends: Vec<String>,
+    pub optdepends: Vec<String>,
+    pub provides: Vec<String>,
+    pub conflicts: Vec<String>,
+    pub replaces: Vec<String>,
+    /// Missing if the package has no `.BUILDINFO`
+    pub build_info: Option<BuildInfo>,
+    /// Paths installed by the package, directories end with `/`
+    #[serde(default)]
+    pub files: Vec<String>,
+}
+
+/// The environment a package was built in, read from its `.BUILDINFO`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BuildInfo {
+    pub packager: String,
+    /// Unix timestamp in seconds
+    pub build_date: i64,
+    /// Name and version of the tool that built the package, e.g. `makepkg 7.0.0`
+    pub build_tool: Option<String>,
+    /// `BUILDENV` of `makepkg.conf`, e.g. `!distcc` or `ccache`
+    pub build_env: Vec<String>,
+    /// `OPTIONS` of `makepkg.conf`, e.g. `strip` or `!debug`
+    pub options: Vec<String>,
+    /// Every package installed during the build, as `<name>-<version>-<arch>`
+    pub installed: Vec<String>,
+}
+
+/// The outcome of publishing a package file to one destination
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ArtifactUpload {
+    /// Name of the publisher
+    pub destination: String,
+    pub file: String,
+    pub sha256: String,
+    pub attempts: u32,
+    /// HTTP status of the last attempt, missing if no response was received or the destination isn't HTTP based
+    pub status: Option<u16>,
+    /// Body of the last response or the error of the last attempt
+    pub response: String,
+    pub success: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -36,7 +129,95 @@ pub struct BuildTaskTransmissionFormat {
     pub source: Option<String>,
     pub subfolder: Option<String>,
     pub options: Option<String>,
-    pub env: Option<Environment>
+    pub env: Option<Environment>,
+    /// Maximum build duration in seconds, overrides the worker's global timeout
+    pub timeout: Option<u64>,
+    /// Resource limits, override the limits of the build profile
+    pub resources: Option<ResourceLimits>,
+    /// Name of the worker build profile to build with
+    pub profile: Option<String>,
+    /// Names of the publishers to publish to, all configured publishers if missing
+    pub publish_to: Option<Vec<String>>,
+    /// Name of the variant of the package this task builds
+    pub variant: Option<String>,
+    /// Whether the worker's caches are used, defaults to `true`
+    pub cache: Option<bool>,
+    /// distcc helpers the build may distribute compile jobs to, as `host:port/jobs`
+    pub distcc_hosts: Option<Vec<String>>,
+    /// Command run after installing the built packages into a fresh container, e.g. `foo --version`
+    pub smoke_test: Option<String>,
+    /// namcap tags accepted for this package, their findings aren't reported
+    pub namcap_allow: Option<Vec<String>>,
+    /// Set if the task rebuilds a published build to check that it's reproducible, instead of building an update
+    pub verify: Option<VerificationRequest>,
+}
+
+/// A build whose published packages are rebuilt and compared byte for byte
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VerificationRequest {
+    pub build_result_id: i32,
+    /// Names of the published package files
+    pub files: Vec<String>,
+}
+
+impl BuildTaskTransmissionFormat {
+    /// Returns the package name, followed by the variant if there is one
+    pub fn display_name(&self) -> String {
+        match &self.variant {
+            Some(variant) => format!("{} ({variant})", self.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+/// A build that is currently running on a worker
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RunningBuild {
+    pub build_id: String,
+    pub package_id: i32,
+    pub name: String,
+    pub variant: Option<String>,
+    pub version: String,
+    pub started_at: NaiveDateTime,
+}
+
+/// Periodically sent by every worker to register itself and report its load
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WorkerHeartbeatTransmissionFormat {
+    pub worker_id: String,
+    pub hostname: String,
+    pub version: String,
+    pub labels: Vec<String>,
+    pub capacity: usize,
+    pub running_builds: Vec<RunningBuild>,
+    /// Set if the worker runs a distcc helper other builds can use
+    pub distcc: Option<DistccHelper>,
+}
+
+/// A distcc daemon offered by a worker
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DistccHelper {
+    /// `host:port` the daemon is reachable at
+    pub address: String,
+    /// Number of jobs the daemon accepts at the same time
+    pub jobs: u32,
+}
+
+impl DistccHelper {
+    /// Returns the entry of this helper in `DISTCC_HOSTS`
+    pub fn host_spec(&self) -> String {
+        format!("{}/{}", self.address, self.jobs)
+    }
+}
+
+/// A part of the output of a running build, published to the build's log stream
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LogChunkTransmissionFormat {
+    pub build_id: String,
+    pub sequence: u64,
+    pub lines: Vec<String>,
+    /// Set on the last chunk of a build
+    pub finished: bool,
 }
 
 type Environment = Vec<EnvironmentVariable>;
@@ -47,11 +228,58 @@ pub struct EnvironmentVariable {
     pub value: String
 }
 
+/// CPU and memory limits applied to a build container
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ResourceLimits {
+    /// Number of CPUs, may be fractional
+    pub cpus: Option<f64>,
+    /// Memory limit in MiB
+    pub memory: Option<u64>,
+    /// Swap available in addition to `memory` in MiB
+    pub swap: Option<u64>,
+    /// Maximum number of processes
+    pub pids_limit: Option<i64>,
+    /// Size of the tmpfs mounted at `/tmp` in MiB
+    pub tmpfs_size: Option<u64>,
+}
+
+impl ResourceLimits {
+    /// Returns these limits with every unset field taken from `fallback`
+    pub fn or(&self, fallback: &ResourceLimits) -> ResourceLimits {
+        ResourceLimits {
+            cpus: self.cpus.or(fallback.cpus),
+            memory: self.memory.or(fallback.memory),
+            swap: self.swap.or(fallback.swap),
+            pids_limit: self.pids_limit.or(fallback.pids_limit),
+            tmpfs_size: self.tmpfs_size.or(fallback.tmpfs_size),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AurPackageSettings {
     pub name: String,
     pub env: Option<Environment>,
     pub options: Option<String>,
+    pub timeout: Option<u64>,
+    pub resources: Option<ResourceLimits>,
+    pub profile: Option<String>,
+    /// Labels a worker needs to have to build this package
+    pub labels: Option<Vec<String>>,
+    /// Names of the publishers the built packages are published to
+    pub publish_to: Option<Vec<String>>,
+    /// Variants the package is built in, each with its own build history
+    pub variants: Option<Vec<PackageVariant>>,
+    /// Set to `false` to build without the worker's caches
+    pub cache: Option<bool>,
+    /// Distribute compile jobs to the distcc helpers of the workers
+    pub distcc: Option<bool>,
+    /// Command that has to succeed after installing the built packages, otherwise they aren't published
+    pub smoke_test: Option<String>,
+    /// namcap tags that are known and accepted, e.g. `missing-contributor`
+    pub namcap_allow: Option<Vec<String>>,
+    /// Rebuild published packages later and check that they are identical
+    pub verify_reproducible: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -60,4 +288,58 @@ pub struct GitPackageSettings {
     pub subfolder: Option<String>,
     pub env: Option<Environment>,
     pub options: Option<String>,
+    pub timeout: Option<u64>,
+    pub resources: Option<ResourceLimits>,
+    pub profile: Option<String>,
+    /// Labels a worker needs to have to build this package
+    pub labels: Option<Vec<String>>,
+    /// Names of the publishers the built packages are published to
+    pub publish_to: Option<Vec<String>>,
+    /// Variants the package is built in, each with its own build history
+    pub variants: Option<Vec<PackageVariant>>,
+    /// Set to `false` to build without the worker's caches
+    pub cache: Option<bool>,
+    /// Distribute compile jobs to the distcc helpers of the workers
+    pub distcc: Option<bool>,
+    /// Command that has to succeed after installing the built packages, otherwise they aren't published
+    pub smoke_test: Option<String>,
+    /// namcap tags that are known and accepted, e.g. `missing-contributor`
+    pub namcap_allow: Option<Vec<String>>,
+    /// Rebuild published packages later and check that they are identical
+    pub verify_reproducible: Option<bool>,
+}
+
+/// A variant of a package, its settings replace the ones of the package.
+///
+/// The environment is added to the package's environment instead.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PackageVariant {
+    pub name: String,
+    pub env: Option<Environment>,
+    pub options: Option<String>,
+    pub profile: Option<String>,
+    /// Labels a worker needs to have to build this variant
+    pub labels: Option<Vec<String>>,
+    /// Names of the publishers the packages of this variant are published to
+    pub publish_to: Option<Vec<String>>,
+}
+
+/// Returns whether a variant name only consists of `[A-Za-z0-9_.-]`.
+///
+/// Variant names are part of build ids, container names and queue names.
+///
+/// # Example
+///
+/// ```
+/// use common::types::is_valid_variant_name;
+/// assert!(is_valid_variant_name("x86_64-v3.lto"));
+/// assert!(!is_valid_variant_name(""));
+/// assert!(!is_valid_variant_name("with space"));
+/// assert!(!is_valid_variant_name("a/b"));
+/// ```
+pub fn is_valid_variant_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
 }
\ No newline at end of file
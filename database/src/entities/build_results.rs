@@ -16,10 +16,39 @@ pub struct Model {
     pub started_at: Option<DateTime>,
     pub finished_at: Option<DateTime>,
     pub version: Option<String>,
+    pub signing_key: Option<String>,
+    /// Checksum of the local repository database the dependencies were installed from
+    pub repo_revision: Option<String>,
+    pub profile: Option<String>,
+    /// Whether the package was built in a fresh chroot
+    pub clean_chroot: bool,
+    /// Compile jobs distributed to the distcc helpers of other workers
+    pub distcc_remote: Option<i64>,
+    /// Compile jobs of a distcc build that ran in the build container
+    pub distcc_local: Option<i64>,
+    /// `pending`, `reproducible`, `unreproducible` or `failed`, missing if the build isn't verified
+    pub reproducible: Option<String>,
+    /// Output of the rebuild of the reproducibility check
+    #[sea_orm(column_type = "Text", nullable)]
+    pub reproducibility_log: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::artifact_uploads::Entity")]
+    ArtifactUploads,
+    #[sea_orm(has_many = "super::build_cache_stats::Entity")]
+    BuildCacheStats,
+    #[sea_orm(has_many = "super::build_dependencies::Entity")]
+    BuildDependencies,
+    #[sea_orm(has_many = "super::build_environments::Entity")]
+    BuildEnvironments,
+    #[sea_orm(has_many = "super::build_phases::Entity")]
+    BuildPhases,
+    #[sea_orm(has_many = "super::built_packages::Entity")]
+    BuiltPackages,
+    #[sea_orm(has_many = "super::lint_findings::Entity")]
+    LintFindings,
     #[sea_orm(
         belongs_to = "super::package_metadata::Entity",
         from = "Column::PackageId",
@@ -28,6 +57,52 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     PackageMetadata,
+    #[sea_orm(has_many = "super::reproducibility_differences::Entity")]
+    ReproducibilityDifferences,
+    #[sea_orm(has_many = "super::smoke_tests::Entity")]
+    SmokeTests,
+}
+
+impl Related<super::artifact_uploads::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ArtifactUploads.def()
+    }
+}
+
+impl Related<super::build_cache_stats::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BuildCacheStats.def()
+    }
+}
+
+impl Related<super::build_dependencies::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BuildDependencies.def()
+    }
+}
+
+impl Related<super::build_environments::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BuildEnvironments.def()
+    }
+}
+
+impl Related<super::build_phases::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BuildPhases.def()
+    }
+}
+
+impl Related<super::built_packages::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BuiltPackages.def()
+    }
+}
+
+impl Related<super::lint_findings::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::LintFindings.def()
+    }
 }
 
 impl Related<super::package_metadata::Entity> for Entity {
@@ -36,4 +111,16 @@ impl Related<super::package_metadata::Entity> for Entity {
     }
 }
 
+impl Related<super::reproducibility_differences::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ReproducibilityDifferences.def()
+    }
+}
+
+impl Related<super::smoke_tests::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SmokeTests.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
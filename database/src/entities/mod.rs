@@ -2,5 +2,16 @@
 
 pub mod prelude;
 
+pub mod artifact_uploads;
+pub mod build_cache_stats;
+pub mod build_dependencies;
+pub mod build_environments;
+pub mod build_phases;
 pub mod build_results;
+pub mod built_packages;
+pub mod lint_findings;
+pub mod package_files;
 pub mod package_metadata;
+pub mod reproducibility_differences;
+pub mod smoke_tests;
+pub mod workers;
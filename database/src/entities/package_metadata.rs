@@ -14,6 +14,7 @@ pub struct Model {
     pub last_modified: i64,
     pub source: Option<String>,
     pub subfolder: Option<String>,
+    pub variant: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
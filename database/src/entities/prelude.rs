@@ -1,4 +1,15 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.3
 
+pub use super::artifact_uploads::Entity as ArtifactUploads;
+pub use super::build_cache_stats::Entity as BuildCacheStats;
+pub use super::build_dependencies::Entity as BuildDependencies;
+pub use super::build_environments::Entity as BuildEnvironments;
+pub use super::build_phases::Entity as BuildPhases;
 pub use super::build_results::Entity as BuildResults;
+pub use super::built_packages::Entity as BuiltPackages;
+pub use super::lint_findings::Entity as LintFindings;
+pub use super::package_files::Entity as PackageFiles;
 pub use super::package_metadata::Entity as PackageMetadata;
+pub use super::reproducibility_differences::Entity as ReproducibilityDifferences;
+pub use super::smoke_tests::Entity as SmokeTests;
+pub use super::workers::Entity as Workers;
@@ -1,11 +1,15 @@
 use log::{error, LevelFilter};
 use sea_orm::{
-    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectOptions,
-    DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    ActiveModelTrait, ActiveValue, ColumnTrait, Condition, ConnectOptions,
+    DatabaseConnection, DatabaseTransaction, DbErr, EntityTrait, FromQueryResult, JoinType, QueryFilter, QueryOrder,
+    QuerySelect, RelationTrait, TransactionTrait,
 };
+use sea_orm::sea_query::{Expr, LikeExpr, Query};
+use serde::Serialize;
+use sea_orm::sqlx::types::chrono::{DateTime, NaiveDateTime, Utc};
 use sea_orm_migration::MigratorTrait;
 use std::process::exit;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 use tokio::time::sleep;
 
 pub mod entities;
@@ -13,11 +17,30 @@ pub mod migrator;
 
 use entities::*;
 use common::environment::get_environment_variable;
-use common::types::{BuildResultTransmissionFormat, PackageSearchResult};
+use common::types::{
+    BuildResultTransmissionFormat, DistccHelper, PackageSearchResult,
+    WorkerHeartbeatTransmissionFormat,
+};
 use common::{CONNECTION_RETRY_NUMBER, RETRY_TIMEOUT};
 use entities::prelude::*;
 use migrator::Migrator;
 
+/// How many file paths of a package are inserted with one statement
+const FILE_INSERT_BATCH: usize = 1000;
+
+/// A file installed by the latest successful build of a package, found by [`Database::find_file_owners`]
+#[derive(Debug, Clone, FromQueryResult, Serialize)]
+pub struct FileOwner {
+    pub path: String,
+    /// Name of the package installing the file, which differs from the built package for split packages
+    pub name: String,
+    pub version: String,
+    pub arch: String,
+    pub file: String,
+    pub package_id: i64,
+    pub build_result_id: i64,
+}
+
 /// The `Database` struct represents a database connection.
 ///
 /// It contains methods to create a new database connection, apply migrations, and update metadata.
@@ -39,9 +62,11 @@ impl Database {
     ///
     /// # Example
     ///
-    /// ```
+    /// ```no_run
+    /// # async fn example() {
     /// use database::Database;
     /// let db = Database::new("postgres://localhost/test".parse().unwrap()).await.unwrap();
+    /// # }
     /// ```
     pub async fn new(url: String) -> Result<Self, DbErr> {
         let mut options = ConnectOptions::new(url);
@@ -54,8 +79,10 @@ impl Database {
     ///
     /// # Example
     ///
-    /// ```
+    /// ```no_run
+    /// # async fn example(db: database::Database) {
     /// db.migrate().await;
+    /// # }
     /// ```
     pub async fn migrate(&self) {
         println!("Applying migrations...");
@@ -74,14 +101,16 @@ impl Database {
     ///
     /// # Example
     ///
-    /// ```
+    /// ```no_run
+    /// # async fn example(db: database::Database, data: common::types::PackageSearchResult) {
     /// let updated = db.update_metadata(&data).await;
+    /// # }
     /// ```
     pub async fn update_metadata(&self, data: &PackageSearchResult) -> bool {
         let mut new_timestamp = false;
 
         let existing =
-            self.get_package_by_name(&data.name)
+            self.get_package_by_name_and_variant(&data.name, &data.variant)
                 .await.unwrap();
 
         let mut db_data = package_metadata::ActiveModel {
@@ -92,6 +121,7 @@ impl Database {
             last_modified: ActiveValue::Set(data.last_modified.to_owned()),
             source: ActiveValue::Set(data.source.to_owned()),
             subfolder: ActiveValue::Set(data.subfolder.to_owned()),
+            variant: ActiveValue::Set(data.variant.to_owned()),
         };
 
         if let Some(m) = existing {
@@ -123,6 +153,42 @@ impl Database {
             .await
     }
 
+    /// Deletes the rows of a package whose variant isn't configured anymore, together with their builds.
+    ///
+    /// With variants, this includes the row the package was built in before they were added.
+    /// Without variants, all variant rows are removed. Returns the number of deleted rows.
+    pub async fn remove_stale_variants(&self, name: &str, variants: &[String]) -> Result<u64, DbErr> {
+        let stale = match variants.is_empty() {
+            true => Condition::all().add(package_metadata::Column::Variant.is_not_null()),
+            false => Condition::any()
+                .add(package_metadata::Column::Variant.is_null())
+                .add(package_metadata::Column::Variant.is_not_in(variants.iter().cloned())),
+        };
+        let deleted = PackageMetadata::delete_many()
+            .filter(package_metadata::Column::Name.eq(name))
+            .filter(stale)
+            .exec(&self.db)
+            .await?;
+        Ok(deleted.rows_affected)
+    }
+
+    /// Returns the package tracking the given variant, or the package without variants if `variant` is `None`
+    pub async fn get_package_by_name_and_variant(
+        &self,
+        name: &String,
+        variant: &Option<String>,
+    ) -> Result<Option<package_metadata::Model>, DbErr> {
+        let variant_condition = match variant {
+            Some(v) => package_metadata::Column::Variant.eq(v),
+            None => package_metadata::Column::Variant.is_null(),
+        };
+        PackageMetadata::find()
+            .filter(package_metadata::Column::Name.eq(name))
+            .filter(variant_condition)
+            .one(&self.db)
+            .await
+    }
+
     pub async fn reset_package_last_modified(&self, id: i32) {
         let p = PackageMetadata::find_by_id(id).one(&self.db).await.unwrap().unwrap();
         let mut am = package_metadata::ActiveModel::from(p);
@@ -147,15 +213,29 @@ impl Database {
         BuildResults::find_by_id(build_result_id).one(&self.db).await
     }
 
+    /// Stores a build result with everything the worker reported in one transaction.
+    ///
+    /// The file lists of older builds of the package are removed once a build published new packages.
     pub async fn save_build_results(
         &self,
         data: &BuildResultTransmissionFormat,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let package = PackageMetadata::find()
+        // a failing insert must not leave a partial build behind, the result is delivered again
+        let txn = self.db.begin().await?;
+        if let Some(verify) = &data.task.verify {
+            self.save_verification(&txn, verify.build_result_id, data).await?;
+            txn.commit().await?;
+            return Ok(());
+        }
+        let Some(package) = PackageMetadata::find()
             .filter(package_metadata::Column::Id.eq(data.task.id.clone()))
-            .one(&self.db)
+            .one(&txn)
             .await?
-            .unwrap();
+        else {
+            // e.g. a variant removed from the config while it was built
+            error!("Package {} ({}) was built, but doesn't exist anymore", data.task.name, data.task.id);
+            return Ok(());
+        };
         let db_data = build_results::ActiveModel {
             id: ActiveValue::NotSet,
             package_id: ActiveValue::Set(package.id as i64),
@@ -165,11 +245,547 @@ impl Database {
             finished_at: ActiveValue::Set(Some(data.timestamps.end)),
             started_at: ActiveValue::Set(Some(data.timestamps.start)),
             version: ActiveValue::Set(Some(data.task.version.clone())),
+            signing_key: ActiveValue::Set(data.signing_key.clone()),
+            repo_revision: ActiveValue::Set(
+                data.report
+                    .as_ref()
+                    .and_then(|r| r.local_repo_revision.clone()),
+            ),
+            profile: ActiveValue::Set(data.profile.clone()),
+            clean_chroot: ActiveValue::Set(data.report.as_ref().is_some_and(|r| r.clean_chroot)),
+            distcc_remote: ActiveValue::Set(
+                data.report
+                    .as_ref()
+                    .and_then(|r| r.distcc.as_ref())
+                    .map(|d| d.remote as i64),
+            ),
+            distcc_local: ActiveValue::Set(
+                data.report
+                    .as_ref()
+                    .and_then(|r| r.distcc.as_ref())
+                    .map(|d| d.local as i64),
+            ),
+            reproducible: ActiveValue::Set(None),
+            reproducibility_log: ActiveValue::Set(None),
+        };
+        let build_result = db_data.insert(&txn).await?;
+
+        if let Some(report) = &data.report {
+            for phase in &report.phases {
+                let phase_data = build_phases::ActiveModel {
+                    id: ActiveValue::NotSet,
+                    build_result_id: ActiveValue::Set(build_result.id as i64),
+                    phase: ActiveValue::Set(phase.phase.as_str().to_string()),
+                    started_at: ActiveValue::Set(millis_to_datetime(phase.started_at)),
+                    finished_at: ActiveValue::Set(millis_to_datetime(phase.finished_at)),
+                    exit_status: ActiveValue::Set(phase.exit_status),
+                    failing_item: ActiveValue::Set(phase.failing_item.clone()),
+                };
+                phase_data.insert(&txn).await?;
+            }
+            for dependency in report.install_plan.iter().flat_map(|p| &p.dependencies) {
+                let dependency_data = build_dependencies::ActiveModel {
+                    id: ActiveValue::NotSet,
+                    build_result_id: ActiveValue::Set(build_result.id as i64),
+                    dependency: ActiveValue::Set(dependency.dependency.clone()),
+                    kind: ActiveValue::Set(dependency.kind.as_str().to_string()),
+                    source: ActiveValue::Set(dependency.source.as_str().to_string()),
+                    repo: ActiveValue::Set(dependency.repo.clone()),
+                };
+                dependency_data.insert(&txn).await?;
+            }
+            for stats in &report.cache_stats {
+                let stats_data = build_cache_stats::ActiveModel {
+                    id: ActiveValue::NotSet,
+                    build_result_id: ActiveValue::Set(build_result.id as i64),
+                    cache: ActiveValue::Set(stats.cache.clone()),
+                    hits: ActiveValue::Set(stats.hits as i64),
+                    misses: ActiveValue::Set(stats.misses as i64),
+                };
+                stats_data.insert(&txn).await?;
+            }
+            for finding in &report.lint_findings {
+                let finding_data = lint_findings::ActiveModel {
+                    id: ActiveValue::NotSet,
+                    build_result_id: ActiveValue::Set(build_result.id as i64),
+                    target: ActiveValue::Set(finding.target.clone()),
+                    severity: ActiveValue::Set(finding.severity.as_str().to_string()),
+                    tag: ActiveValue::Set(finding.tag.clone()),
+                    message: ActiveValue::Set(finding.message.clone()),
+                };
+                finding_data.insert(&txn).await?;
+            }
+        }
+
+        for upload in &data.uploads {
+            let upload_data = artifact_uploads::ActiveModel {
+                id: ActiveValue::NotSet,
+                build_result_id: ActiveValue::Set(build_result.id as i64),
+                destination: ActiveValue::Set(upload.destination.clone()),
+                file: ActiveValue::Set(upload.file.clone()),
+                sha256: ActiveValue::Set(upload.sha256.clone()),
+                attempts: ActiveValue::Set(upload.attempts as i32),
+                status: ActiveValue::Set(upload.status.map(|s| s as i32)),
+                response: ActiveValue::Set(Some(upload.response.clone())),
+                success: ActiveValue::Set(upload.success),
+            };
+            upload_data.insert(&txn).await?;
+        }
+
+        if let Some(test) = &data.smoke_test {
+            let test_data = smoke_tests::ActiveModel {
+                id: ActiveValue::NotSet,
+                build_result_id: ActiveValue::Set(build_result.id as i64),
+                command: ActiveValue::Set(test.command.clone()),
+                exit_code: ActiveValue::Set(test.exit_code),
+                success: ActiveValue::Set(test.success),
+                log: ActiveValue::Set(Some(test.log_lines.join(""))),
+            };
+            test_data.insert(&txn).await?;
+        }
+
+        for package in &data.packages {
+            let package_data = built_packages::ActiveModel {
+                id: ActiveValue::NotSet,
+                build_result_id: ActiveValue::Set(build_result.id as i64),
+                file: ActiveValue::Set(package.file.clone()),
+                name: ActiveValue::Set(package.name.clone()),
+                version: ActiveValue::Set(package.version.clone()),
+                arch: ActiveValue::Set(package.arch.clone()),
+                description: ActiveValue::Set(package.description.clone()),
+                url: ActiveValue::Set(package.url.clone()),
+                size: ActiveValue::Set(package.size),
+                licenses: ActiveValue::Set(package.licenses.join("\n")),
+                depends: ActiveValue::Set(package.depends.join("\n")),
+                optdepends: ActiveValue::Set(package.optdepends.join("\n")),
+                provides: ActiveValue::Set(package.provides.join("\n")),
+                conflicts: ActiveValue::Set(package.conflicts.join("\n")),
+                replaces: ActiveValue::Set(package.replaces.join("\n")),
+            };
+            let built_package = package_data.insert(&txn).await?;
+            // a package can install thousands of files, so they are inserted in batches
+            for paths in package.files.chunks(FILE_INSERT_BATCH) {
+                PackageFiles::insert_many(paths.iter().map(|path| package_files::ActiveModel {
+                    id: ActiveValue::NotSet,
+                    built_package_id: ActiveValue::Set(built_package.id),
+                    path: ActiveValue::Set(path.clone()),
+                }))
+                .exec(&txn)
+                .await?;
+            }
+        }
+
+        // split packages share the build, so the environment of the first one stands for all
+        if let Some(info) = data.packages.iter().find_map(|p| p.build_info.as_ref()) {
+            let environment_data = build_environments::ActiveModel {
+                id: ActiveValue::NotSet,
+                build_result_id: ActiveValue::Set(build_result.id as i64),
+                packager: ActiveValue::Set(info.packager.clone()),
+                build_date: ActiveValue::Set(info.build_date),
+                build_tool: ActiveValue::Set(info.build_tool.clone()),
+                build_env: ActiveValue::Set(info.build_env.join("\n")),
+                options: ActiveValue::Set(info.options.join("\n")),
+                installed: ActiveValue::Set(info.installed.join("\n")),
+            };
+            environment_data.insert(&txn).await?;
+        }
+
+        // only the latest build of a package is searched, so the file lists of older builds are dropped
+        if data.success && !data.packages.is_empty() {
+            let older_builds = Query::select()
+                .column(build_results::Column::Id)
+                .from(BuildResults)
+                .and_where(build_results::Column::PackageId.eq(package.id))
+                .and_where(build_results::Column::Id.ne(build_result.id))
+                .to_owned();
+            let older_packages = Query::select()
+                .column(built_packages::Column::Id)
+                .from(BuiltPackages)
+                .and_where(built_packages::Column::BuildResultId.in_subquery(older_builds))
+                .to_owned();
+            PackageFiles::delete_many()
+                .filter(package_files::Column::BuiltPackageId.in_subquery(older_packages))
+                .exec(&txn)
+                .await?;
+        }
+
+        txn.commit().await?;
+        Ok(())
+    }
+
+    /// Stores the outcome of a reproducibility check on the build it rebuilt
+    async fn save_verification(
+        &self,
+        txn: &DatabaseTransaction,
+        build_result_id: i32,
+        data: &BuildResultTransmissionFormat,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(build_result) = BuildResults::find_by_id(build_result_id).one(txn).await? else {
+            error!("Build {build_result_id} was verified, but doesn't exist anymore");
+            return Ok(());
         };
-        db_data.insert(&self.db).await?;
+        let files = data
+            .report
+            .as_ref()
+            .map(|r| r.reproducibility.as_slice())
+            .unwrap_or_default();
+        let status = if !data.success || data.status_code != 0 || files.is_empty() {
+            "failed"
+        } else if files.iter().all(|f| f.reproducible()) {
+            "reproducible"
+        } else {
+            "unreproducible"
+        };
+
+        let mut build_result = build_results::ActiveModel::from(build_result);
+        build_result.reproducible = ActiveValue::Set(Some(status.to_string()));
+        build_result.reproducibility_log = ActiveValue::Set(Some(data.log_lines.join("")));
+        build_result.update(txn).await?;
+
+        ReproducibilityDifferences::delete_many()
+            .filter(reproducibility_differences::Column::BuildResultId.eq(build_result_id as i64))
+            .exec(txn)
+            .await?;
+        for file in files {
+            let mut differences: Vec<(String, &str)> = file
+                .differences
+                .iter()
+                .map(|d| (d.path.clone(), d.change.as_str()))
+                .collect();
+            if file.rebuilt_sha256.is_none() {
+                differences.push((String::new(), "not_rebuilt"));
+            }
+            for (path, change) in differences {
+                let difference_data = reproducibility_differences::ActiveModel {
+                    id: ActiveValue::NotSet,
+                    build_result_id: ActiveValue::Set(build_result_id as i64),
+                    file: ActiveValue::Set(file.name.clone()),
+                    path: ActiveValue::Set(path),
+                    change: ActiveValue::Set(change.to_string()),
+                };
+                difference_data.insert(txn).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the most recent successful build of a package if it finished more than `delay` ago and isn't verified yet.
+    ///
+    /// Older builds are never verified, their packages were replaced in the repositories.
+    pub async fn get_build_to_verify(
+        &self,
+        package_id: i32,
+        delay: Duration,
+    ) -> Result<Option<build_results::Model>, DbErr> {
+        let threshold = DateTime::<Utc>::from(SystemTime::now() - delay).naive_utc();
+        let latest = BuildResults::find()
+            .filter(build_results::Column::PackageId.eq(package_id))
+            .filter(build_results::Column::Success.eq(true))
+            .order_by_desc(build_results::Column::FinishedAt)
+            .one(&self.db)
+            .await?;
+        Ok(latest.filter(|build| {
+            build.reproducible.is_none() && build.finished_at.is_some_and(|finished| finished < threshold)
+        }))
+    }
+
+    /// Returns the package files a build published successfully, without their signatures
+    pub async fn get_published_files(&self, build_result_id: i32) -> Result<Vec<String>, DbErr> {
+        let mut files: Vec<String> = self
+            .get_artifact_uploads(vec![build_result_id])
+            .await?
+            .into_iter()
+            .filter(|u| u.success && !u.file.ends_with(".sig"))
+            .map(|u| u.file)
+            .collect();
+        files.sort();
+        files.dedup();
+        Ok(files)
+    }
 
+    /// Sets the reproducibility status of a build, e.g. to `pending` once a check is scheduled.
+    ///
+    /// A status the build already has, like the outcome of a check that finished early, is kept.
+    pub async fn set_reproducibility_status(&self, build_result_id: i32, status: &str) -> Result<(), DbErr> {
+        BuildResults::update_many()
+            .col_expr(build_results::Column::Reproducible, Expr::value(status))
+            .filter(build_results::Column::Id.eq(build_result_id))
+            .filter(build_results::Column::Reproducible.is_null())
+            .exec(&self.db)
+            .await?;
         Ok(())
     }
+
+    /// Returns the files that differ between the published and the rebuilt packages of the given builds
+    pub async fn get_reproducibility_differences(
+        &self,
+        build_result_ids: Vec<i32>,
+    ) -> Result<Vec<reproducibility_differences::Model>, DbErr> {
+        ReproducibilityDifferences::find()
+            .filter(
+                reproducibility_differences::Column::BuildResultId
+                    .is_in(build_result_ids.into_iter().map(|id| id as i64)),
+            )
+            .order_by_asc(reproducibility_differences::Column::Id)
+            .all(&self.db)
+            .await
+    }
+
+    /// Returns the publish outcomes of the artifacts of the given builds
+    pub async fn get_artifact_uploads(
+        &self,
+        build_result_ids: Vec<i32>,
+    ) -> Result<Vec<artifact_uploads::Model>, DbErr> {
+        ArtifactUploads::find()
+            .filter(
+                artifact_uploads::Column::BuildResultId
+                    .is_in(build_result_ids.into_iter().map(|id| id as i64)),
+            )
+            .order_by_asc(artifact_uploads::Column::Id)
+            .all(&self.db)
+            .await
+    }
+
+    /// Returns the namcap findings of the given builds
+    pub async fn get_lint_findings(
+        &self,
+        build_result_ids: Vec<i32>,
+    ) -> Result<Vec<lint_findings::Model>, DbErr> {
+        LintFindings::find()
+            .filter(
+                lint_findings::Column::BuildResultId
+                    .is_in(build_result_ids.into_iter().map(|id| id as i64)),
+            )
+            .order_by_asc(lint_findings::Column::Id)
+            .all(&self.db)
+            .await
+    }
+
+    /// Returns the metadata of the package files of the given builds
+    pub async fn get_built_packages(
+        &self,
+        build_result_ids: Vec<i32>,
+    ) -> Result<Vec<built_packages::Model>, DbErr> {
+        BuiltPackages::find()
+            .filter(
+                built_packages::Column::BuildResultId
+                    .is_in(build_result_ids.into_iter().map(|id| id as i64)),
+            )
+            .order_by_asc(built_packages::Column::Id)
+            .all(&self.db)
+            .await
+    }
+
+    /// Returns the packages whose latest successful build installs the given path, like `pacman -F`.
+    ///
+    /// Only the latest build of a package keeps its file list, see [`Database::save_build_results`].
+    ///
+    /// A term without `/` is matched against the file names, any other term against the whole path.
+    pub async fn find_file_owners(&self, term: &str, limit: u64) -> Result<Vec<FileOwner>, DbErr> {
+        let term = term.trim().trim_start_matches('/');
+        let condition = match term.contains('/') {
+            true => Condition::all().add(package_files::Column::Path.eq(term)),
+            false => {
+                let escaped = term.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+                Condition::any()
+                    .add(package_files::Column::Path.eq(term))
+                    .add(package_files::Column::Path.like(LikeExpr::new(format!("%/{escaped}")).escape('\\')))
+            }
+        };
+        let latest_builds = Query::select()
+            .expr(Expr::col(build_results::Column::Id).max())
+            .from(BuildResults)
+            .and_where(build_results::Column::Success.eq(true))
+            .group_by_col(build_results::Column::PackageId)
+            .to_owned();
+
+        PackageFiles::find()
+            .select_only()
+            .column(package_files::Column::Path)
+            .column(built_packages::Column::Name)
+            .column(built_packages::Column::Version)
+            .column(built_packages::Column::Arch)
+            .column(built_packages::Column::File)
+            .column(build_results::Column::PackageId)
+            .column(built_packages::Column::BuildResultId)
+            .join(JoinType::InnerJoin, package_files::Relation::BuiltPackages.def())
+            .join(JoinType::InnerJoin, built_packages::Relation::BuildResults.def())
+            .filter(built_packages::Column::BuildResultId.in_subquery(latest_builds))
+            .filter(condition)
+            .order_by_asc(package_files::Column::Path)
+            .order_by_asc(built_packages::Column::Name)
+            .limit(limit)
+            .into_model::<FileOwner>()
+            .all(&self.db)
+            .await
+    }
+
+    /// Returns the build environments of the given builds
+    pub async fn get_build_environments(
+        &self,
+        build_result_ids: Vec<i32>,
+    ) -> Result<Vec<build_environments::Model>, DbErr> {
+        BuildEnvironments::find()
+            .filter(
+                build_environments::Column::BuildResultId
+                    .is_in(build_result_ids.into_iter().map(|id| id as i64)),
+            )
+            .order_by_asc(build_environments::Column::Id)
+            .all(&self.db)
+            .await
+    }
+
+    /// Returns the smoke test outcomes of the given builds
+    pub async fn get_smoke_tests(
+        &self,
+        build_result_ids: Vec<i32>,
+    ) -> Result<Vec<smoke_tests::Model>, DbErr> {
+        SmokeTests::find()
+            .filter(
+                smoke_tests::Column::BuildResultId
+                    .is_in(build_result_ids.into_iter().map(|id| id as i64)),
+            )
+            .order_by_asc(smoke_tests::Column::Id)
+            .all(&self.db)
+            .await
+    }
+
+    /// Returns the cache statistics of the given builds
+    pub async fn get_build_cache_stats(
+        &self,
+        build_result_ids: Vec<i32>,
+    ) -> Result<Vec<build_cache_stats::Model>, DbErr> {
+        BuildCacheStats::find()
+            .filter(
+                build_cache_stats::Column::BuildResultId
+                    .is_in(build_result_ids.into_iter().map(|id| id as i64)),
+            )
+            .order_by_asc(build_cache_stats::Column::Id)
+            .all(&self.db)
+            .await
+    }
+
+    /// Returns the install plans of the given builds
+    pub async fn get_build_dependencies(
+        &self,
+        build_result_ids: Vec<i32>,
+    ) -> Result<Vec<build_dependencies::Model>, DbErr> {
+        BuildDependencies::find()
+            .filter(
+                build_dependencies::Column::BuildResultId
+                    .is_in(build_result_ids.into_iter().map(|id| id as i64)),
+            )
+            .order_by_asc(build_dependencies::Column::Id)
+            .all(&self.db)
+            .await
+    }
+
+    /// Returns the phases of the given builds, ordered by their start
+    pub async fn get_build_phases(
+        &self,
+        build_result_ids: Vec<i32>,
+    ) -> Result<Vec<build_phases::Model>, DbErr> {
+        BuildPhases::find()
+            .filter(
+                build_phases::Column::BuildResultId
+                    .is_in(build_result_ids.into_iter().map(|id| id as i64)),
+            )
+            .order_by_asc(build_phases::Column::StartedAt)
+            .all(&self.db)
+            .await
+    }
+
+    /// Registers a worker or updates its state from a heartbeat.
+    ///
+    /// The worker is marked as online and its last seen time is set to now.
+    pub async fn save_worker_heartbeat(
+        &self,
+        heartbeat: &WorkerHeartbeatTransmissionFormat,
+    ) -> Result<(), DbErr> {
+        let now = Utc::now().naive_utc();
+        let existing = Workers::find_by_id(heartbeat.worker_id.clone())
+            .one(&self.db)
+            .await?;
+
+        let mut db_data = workers::ActiveModel {
+            id: ActiveValue::Set(heartbeat.worker_id.clone()),
+            hostname: ActiveValue::Set(heartbeat.hostname.clone()),
+            version: ActiveValue::Set(heartbeat.version.clone()),
+            labels: ActiveValue::Set(heartbeat.labels.join(", ")),
+            capacity: ActiveValue::Set(heartbeat.capacity as i32),
+            running_builds: ActiveValue::Set(heartbeat.running_builds.len() as i32),
+            current_builds: ActiveValue::Set(serde_json::to_string(&heartbeat.running_builds).ok()),
+            online: ActiveValue::Set(true),
+            registered_at: ActiveValue::Set(now),
+            last_seen: ActiveValue::Set(now),
+            distcc_address: ActiveValue::Set(heartbeat.distcc.as_ref().map(|d| d.address.clone())),
+            distcc_jobs: ActiveValue::Set(heartbeat.distcc.as_ref().map(|d| d.jobs as i32)),
+        };
+
+        match existing {
+            Some(w) => {
+                db_data.registered_at = ActiveValue::Set(w.registered_at);
+                db_data.update(&self.db).await?;
+            }
+            None => {
+                db_data.insert(&self.db).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Marks all workers as offline that haven't sent a heartbeat within `timeout`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<u64, DbErr>` - The number of workers that went offline.
+    pub async fn mark_offline_workers(&self, timeout: Duration) -> Result<u64, DbErr> {
+        let threshold = DateTime::<Utc>::from(SystemTime::now() - timeout).naive_utc();
+        let result = Workers::update_many()
+            .col_expr(workers::Column::Online, Expr::value(false))
+            .col_expr(workers::Column::RunningBuilds, Expr::value(0))
+            .col_expr(workers::Column::CurrentBuilds, Expr::value(Option::<String>::None))
+            .filter(workers::Column::Online.eq(true))
+            .filter(workers::Column::LastSeen.lt(threshold))
+            .exec(&self.db)
+            .await?;
+
+        Ok(result.rows_affected)
+    }
+
+    /// Returns the distcc helpers of all online workers
+    pub async fn get_distcc_helpers(&self) -> Result<Vec<DistccHelper>, DbErr> {
+        let workers = Workers::find()
+            .filter(workers::Column::Online.eq(true))
+            .filter(workers::Column::DistccAddress.is_not_null())
+            .order_by_asc(workers::Column::Id)
+            .all(&self.db)
+            .await?;
+        Ok(workers
+            .into_iter()
+            .filter_map(|w| {
+                Some(DistccHelper {
+                    address: w.distcc_address?,
+                    jobs: w.distcc_jobs.unwrap_or(1).max(1) as u32,
+                })
+            })
+            .collect())
+    }
+
+    pub async fn get_workers(&self) -> Result<Vec<workers::Model>, DbErr> {
+        Workers::find()
+            .order_by_asc(workers::Column::Id)
+            .all(&self.db)
+            .await
+    }
+}
+
+fn millis_to_datetime(millis: i64) -> NaiveDateTime {
+    DateTime::from_timestamp_millis(millis)
+        .unwrap_or_default()
+        .naive_utc()
 }
 
 pub async fn connect_to_db() -> Database {
@@ -0,0 +1,53 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Workers::Table)
+                    .col(
+                        ColumnDef::new(Workers::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Workers::Hostname).string().not_null())
+                    .col(ColumnDef::new(Workers::Version).string().not_null())
+                    .col(ColumnDef::new(Workers::Labels).string().not_null())
+                    .col(ColumnDef::new(Workers::Capacity).integer().not_null())
+                    .col(ColumnDef::new(Workers::RunningBuilds).integer().not_null())
+                    .col(ColumnDef::new(Workers::CurrentBuilds).text().null())
+                    .col(ColumnDef::new(Workers::Online).boolean().not_null())
+                    .col(ColumnDef::new(Workers::RegisteredAt).date_time().not_null())
+                    .col(ColumnDef::new(Workers::LastSeen).date_time().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Workers::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum Workers {
+    Table,
+    Id,
+    Hostname,
+    Version,
+    Labels,
+    Capacity,
+    RunningBuilds,
+    CurrentBuilds,
+    Online,
+    RegisteredAt,
+    LastSeen,
+}
@@ -0,0 +1,65 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(BuildPhases::Table)
+                    .col(
+                        ColumnDef::new(BuildPhases::Id)
+                            .integer()
+                            .not_null()
+                            .primary_key()
+                            .auto_increment(),
+                    )
+                    .col(
+                        ColumnDef::new(BuildPhases::BuildResultId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(BuildPhases::Table, BuildPhases::BuildResultId)
+                            .to(BuildResults::Table, BuildResults::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(BuildPhases::Phase).string().not_null())
+                    .col(ColumnDef::new(BuildPhases::StartedAt).date_time().not_null())
+                    .col(ColumnDef::new(BuildPhases::FinishedAt).date_time().not_null())
+                    .col(ColumnDef::new(BuildPhases::ExitStatus).integer().not_null())
+                    .col(ColumnDef::new(BuildPhases::FailingItem).string().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BuildPhases::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum BuildPhases {
+    Table,
+    Id,
+    BuildResultId,
+    Phase,
+    StartedAt,
+    FinishedAt,
+    ExitStatus,
+    FailingItem,
+}
+
+#[derive(Iden)]
+pub enum BuildResults {
+    Table,
+    Id,
+}
@@ -0,0 +1,69 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ArtifactUploads::Table)
+                    .col(
+                        ColumnDef::new(ArtifactUploads::Id)
+                            .integer()
+                            .not_null()
+                            .primary_key()
+                            .auto_increment(),
+                    )
+                    .col(
+                        ColumnDef::new(ArtifactUploads::BuildResultId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(ArtifactUploads::Table, ArtifactUploads::BuildResultId)
+                            .to(BuildResults::Table, BuildResults::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(ArtifactUploads::Destination).string().not_null())
+                    .col(ColumnDef::new(ArtifactUploads::File).string().not_null())
+                    .col(ColumnDef::new(ArtifactUploads::Sha256).string().not_null())
+                    .col(ColumnDef::new(ArtifactUploads::Attempts).integer().not_null())
+                    .col(ColumnDef::new(ArtifactUploads::Status).integer().null())
+                    .col(ColumnDef::new(ArtifactUploads::Response).text().null())
+                    .col(ColumnDef::new(ArtifactUploads::Success).boolean().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ArtifactUploads::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum ArtifactUploads {
+    Table,
+    Id,
+    BuildResultId,
+    Destination,
+    File,
+    Sha256,
+    Attempts,
+    Status,
+    Response,
+    Success,
+}
+
+#[derive(Iden)]
+pub enum BuildResults {
+    Table,
+    Id,
+}
@@ -0,0 +1,36 @@
+use sea_orm_migration::prelude::*;
+use crate::entities::prelude::BuildResults;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BuildResults)
+                    .add_column(
+                        ColumnDef::new(Alias::new("repo_revision"))
+                            .string()
+                            .null()
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(BuildResults)
+                .drop_column(Alias::new("repo_revision"))
+                .to_owned(),
+        ).await?;
+
+        Ok(())
+    }
+}
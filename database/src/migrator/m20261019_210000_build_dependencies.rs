@@ -0,0 +1,63 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(BuildDependencies::Table)
+                    .col(
+                        ColumnDef::new(BuildDependencies::Id)
+                            .integer()
+                            .not_null()
+                            .primary_key()
+                            .auto_increment(),
+                    )
+                    .col(
+                        ColumnDef::new(BuildDependencies::BuildResultId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(BuildDependencies::Table, BuildDependencies::BuildResultId)
+                            .to(BuildResults::Table, BuildResults::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(BuildDependencies::Dependency).string().not_null())
+                    .col(ColumnDef::new(BuildDependencies::Kind).string().not_null())
+                    .col(ColumnDef::new(BuildDependencies::Source).string().not_null())
+                    .col(ColumnDef::new(BuildDependencies::Repo).string().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BuildDependencies::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum BuildDependencies {
+    Table,
+    Id,
+    BuildResultId,
+    Dependency,
+    Kind,
+    Source,
+    Repo,
+}
+
+#[derive(Iden)]
+pub enum BuildResults {
+    Table,
+    Id,
+}
@@ -0,0 +1,61 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(BuildCacheStats::Table)
+                    .col(
+                        ColumnDef::new(BuildCacheStats::Id)
+                            .integer()
+                            .not_null()
+                            .primary_key()
+                            .auto_increment(),
+                    )
+                    .col(
+                        ColumnDef::new(BuildCacheStats::BuildResultId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(BuildCacheStats::Table, BuildCacheStats::BuildResultId)
+                            .to(BuildResults::Table, BuildResults::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(BuildCacheStats::Cache).string().not_null())
+                    .col(ColumnDef::new(BuildCacheStats::Hits).big_integer().not_null())
+                    .col(ColumnDef::new(BuildCacheStats::Misses).big_integer().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BuildCacheStats::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum BuildCacheStats {
+    Table,
+    Id,
+    BuildResultId,
+    Cache,
+    Hits,
+    Misses,
+}
+
+#[derive(Iden)]
+pub enum BuildResults {
+    Table,
+    Id,
+}
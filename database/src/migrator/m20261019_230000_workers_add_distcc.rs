@@ -0,0 +1,54 @@
+use sea_orm_migration::prelude::*;
+use crate::entities::prelude::Workers;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Workers)
+                    .add_column(
+                        ColumnDef::new(Alias::new("distcc_address"))
+                            .string()
+                            .null()
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Workers)
+                    .add_column(
+                        ColumnDef::new(Alias::new("distcc_jobs"))
+                            .integer()
+                            .null()
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Workers)
+                .drop_column(Alias::new("distcc_jobs"))
+                .to_owned(),
+        ).await?;
+        manager.alter_table(
+            Table::alter()
+                .table(Workers)
+                .drop_column(Alias::new("distcc_address"))
+                .to_owned(),
+        ).await?;
+
+        Ok(())
+    }
+}
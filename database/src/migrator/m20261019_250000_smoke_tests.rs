@@ -0,0 +1,63 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(SmokeTests::Table)
+                    .col(
+                        ColumnDef::new(SmokeTests::Id)
+                            .integer()
+                            .not_null()
+                            .primary_key()
+                            .auto_increment(),
+                    )
+                    .col(
+                        ColumnDef::new(SmokeTests::BuildResultId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(SmokeTests::Table, SmokeTests::BuildResultId)
+                            .to(BuildResults::Table, BuildResults::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(SmokeTests::Command).string().not_null())
+                    .col(ColumnDef::new(SmokeTests::ExitCode).big_integer().not_null())
+                    .col(ColumnDef::new(SmokeTests::Success).boolean().not_null())
+                    .col(ColumnDef::new(SmokeTests::Log).text().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(SmokeTests::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum SmokeTests {
+    Table,
+    Id,
+    BuildResultId,
+    Command,
+    ExitCode,
+    Success,
+    Log,
+}
+
+#[derive(Iden)]
+pub enum BuildResults {
+    Table,
+    Id,
+}
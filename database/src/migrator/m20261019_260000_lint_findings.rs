@@ -0,0 +1,63 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(LintFindings::Table)
+                    .col(
+                        ColumnDef::new(LintFindings::Id)
+                            .integer()
+                            .not_null()
+                            .primary_key()
+                            .auto_increment(),
+                    )
+                    .col(
+                        ColumnDef::new(LintFindings::BuildResultId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(LintFindings::Table, LintFindings::BuildResultId)
+                            .to(BuildResults::Table, BuildResults::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(LintFindings::Target).string().not_null())
+                    .col(ColumnDef::new(LintFindings::Severity).string().not_null())
+                    .col(ColumnDef::new(LintFindings::Tag).string().not_null())
+                    .col(ColumnDef::new(LintFindings::Message).text().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(LintFindings::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum LintFindings {
+    Table,
+    Id,
+    BuildResultId,
+    Target,
+    Severity,
+    Tag,
+    Message,
+}
+
+#[derive(Iden)]
+pub enum BuildResults {
+    Table,
+    Id,
+}
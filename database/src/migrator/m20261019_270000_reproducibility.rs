@@ -0,0 +1,102 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BuildResults::Table)
+                    .add_column(
+                        ColumnDef::new(BuildResults::Reproducible)
+                            .string()
+                            .null()
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BuildResults::Table)
+                    .add_column(
+                        ColumnDef::new(BuildResults::ReproducibilityLog)
+                            .text()
+                            .null()
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(ReproducibilityDifferences::Table)
+                    .col(
+                        ColumnDef::new(ReproducibilityDifferences::Id)
+                            .integer()
+                            .not_null()
+                            .primary_key()
+                            .auto_increment(),
+                    )
+                    .col(
+                        ColumnDef::new(ReproducibilityDifferences::BuildResultId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(ReproducibilityDifferences::Table, ReproducibilityDifferences::BuildResultId)
+                            .to(BuildResults::Table, BuildResults::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(ReproducibilityDifferences::File).string().not_null())
+                    .col(ColumnDef::new(ReproducibilityDifferences::Path).string().not_null())
+                    .col(ColumnDef::new(ReproducibilityDifferences::Change).string().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ReproducibilityDifferences::Table).to_owned())
+            .await?;
+        manager.alter_table(
+            Table::alter()
+                .table(BuildResults::Table)
+                .drop_column(BuildResults::Reproducible)
+                .to_owned(),
+        ).await?;
+        manager.alter_table(
+            Table::alter()
+                .table(BuildResults::Table)
+                .drop_column(BuildResults::ReproducibilityLog)
+                .to_owned(),
+        ).await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+pub enum ReproducibilityDifferences {
+    Table,
+    Id,
+    BuildResultId,
+    File,
+    Path,
+    Change,
+}
+
+#[derive(Iden)]
+pub enum BuildResults {
+    Table,
+    Id,
+    Reproducible,
+    ReproducibilityLog,
+}
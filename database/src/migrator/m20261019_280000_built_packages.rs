@@ -0,0 +1,130 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(BuiltPackages::Table)
+                    .col(
+                        ColumnDef::new(BuiltPackages::Id)
+                            .integer()
+                            .not_null()
+                            .primary_key()
+                            .auto_increment(),
+                    )
+                    .col(
+                        ColumnDef::new(BuiltPackages::BuildResultId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(BuiltPackages::Table, BuiltPackages::BuildResultId)
+                            .to(BuildResults::Table, BuildResults::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(BuiltPackages::File).string().not_null())
+                    .col(ColumnDef::new(BuiltPackages::Name).string().not_null())
+                    .col(ColumnDef::new(BuiltPackages::Version).string().not_null())
+                    .col(ColumnDef::new(BuiltPackages::Arch).string().not_null())
+                    .col(ColumnDef::new(BuiltPackages::Description).text().null())
+                    .col(ColumnDef::new(BuiltPackages::Url).string().null())
+                    .col(ColumnDef::new(BuiltPackages::Size).big_integer().not_null())
+                    .col(ColumnDef::new(BuiltPackages::Licenses).text().not_null())
+                    .col(ColumnDef::new(BuiltPackages::Depends).text().not_null())
+                    .col(ColumnDef::new(BuiltPackages::Optdepends).text().not_null())
+                    .col(ColumnDef::new(BuiltPackages::Provides).text().not_null())
+                    .col(ColumnDef::new(BuiltPackages::Conflicts).text().not_null())
+                    .col(ColumnDef::new(BuiltPackages::Replaces).text().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(BuildEnvironments::Table)
+                    .col(
+                        ColumnDef::new(BuildEnvironments::Id)
+                            .integer()
+                            .not_null()
+                            .primary_key()
+                            .auto_increment(),
+                    )
+                    .col(
+                        ColumnDef::new(BuildEnvironments::BuildResultId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(BuildEnvironments::Table, BuildEnvironments::BuildResultId)
+                            .to(BuildResults::Table, BuildResults::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(BuildEnvironments::Packager).string().not_null())
+                    .col(ColumnDef::new(BuildEnvironments::BuildDate).big_integer().not_null())
+                    .col(ColumnDef::new(BuildEnvironments::BuildTool).string().null())
+                    .col(ColumnDef::new(BuildEnvironments::BuildEnv).text().not_null())
+                    .col(ColumnDef::new(BuildEnvironments::Options).text().not_null())
+                    .col(ColumnDef::new(BuildEnvironments::Installed).text().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BuildEnvironments::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(BuiltPackages::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum BuiltPackages {
+    Table,
+    Id,
+    BuildResultId,
+    File,
+    Name,
+    Version,
+    Arch,
+    Description,
+    Url,
+    Size,
+    Licenses,
+    Depends,
+    Optdepends,
+    Provides,
+    Conflicts,
+    Replaces,
+}
+
+#[derive(Iden)]
+pub enum BuildEnvironments {
+    Table,
+    Id,
+    BuildResultId,
+    Packager,
+    BuildDate,
+    BuildTool,
+    BuildEnv,
+    Options,
+    Installed,
+}
+
+#[derive(Iden)]
+pub enum BuildResults {
+    Table,
+    Id,
+}
@@ -0,0 +1,77 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PackageFiles::Table)
+                    .col(
+                        ColumnDef::new(PackageFiles::Id)
+                            .integer()
+                            .not_null()
+                            .primary_key()
+                            .auto_increment(),
+                    )
+                    .col(
+                        ColumnDef::new(PackageFiles::BuiltPackageId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(PackageFiles::Table, PackageFiles::BuiltPackageId)
+                            .to(BuiltPackages::Table, BuiltPackages::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(PackageFiles::Path).text().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-package_files-path")
+                    .table(PackageFiles::Table)
+                    .col(PackageFiles::Path)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-package_files-built_package_id")
+                    .table(PackageFiles::Table)
+                    .col(PackageFiles::BuiltPackageId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PackageFiles::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum PackageFiles {
+    Table,
+    Id,
+    BuiltPackageId,
+    Path,
+}
+
+#[derive(Iden)]
+pub enum BuiltPackages {
+    Table,
+    Id,
+}
@@ -6,6 +6,23 @@ mod m20250121_160432_build_results_add_started_at_and_version;
 mod m20250316_173233_source;
 mod m20250316_182755_subfolder;
 mod m20250319_110511_switch_start_and_end_timestamps;
+mod m20261019_120000_workers;
+mod m20261019_140000_build_phases;
+mod m20261019_150000_artifact_uploads;
+mod m20261019_160000_build_results_add_signing_key;
+mod m20261019_170000_build_results_add_repo_revision;
+mod m20261019_180000_build_results_add_profile;
+mod m20261019_190000_package_metadata_add_variant;
+mod m20261019_200000_build_results_add_clean_chroot;
+mod m20261019_210000_build_dependencies;
+mod m20261019_220000_build_cache_stats;
+mod m20261019_230000_workers_add_distcc;
+mod m20261019_240000_build_results_add_distcc;
+mod m20261019_250000_smoke_tests;
+mod m20261019_260000_lint_findings;
+mod m20261019_270000_reproducibility;
+mod m20261019_280000_built_packages;
+mod m20261019_290000_package_files;
 
 use sea_orm_migration::prelude::*;
 
@@ -23,6 +40,23 @@ impl MigratorTrait for Migrator {
             Box::new(m20250316_173233_source::Migration),
             Box::new(m20250316_182755_subfolder::Migration),
             Box::new(m20250319_110511_switch_start_and_end_timestamps::Migration),
+            Box::new(m20261019_120000_workers::Migration),
+            Box::new(m20261019_140000_build_phases::Migration),
+            Box::new(m20261019_150000_artifact_uploads::Migration),
+            Box::new(m20261019_160000_build_results_add_signing_key::Migration),
+            Box::new(m20261019_170000_build_results_add_repo_revision::Migration),
+            Box::new(m20261019_180000_build_results_add_profile::Migration),
+            Box::new(m20261019_190000_package_metadata_add_variant::Migration),
+            Box::new(m20261019_200000_build_results_add_clean_chroot::Migration),
+            Box::new(m20261019_210000_build_dependencies::Migration),
+            Box::new(m20261019_220000_build_cache_stats::Migration),
+            Box::new(m20261019_230000_workers_add_distcc::Migration),
+            Box::new(m20261019_240000_build_results_add_distcc::Migration),
+            Box::new(m20261019_250000_smoke_tests::Migration),
+            Box::new(m20261019_260000_lint_findings::Migration),
+            Box::new(m20261019_270000_reproducibility::Migration),
+            Box::new(m20261019_280000_built_packages::Migration),
+            Box::new(m20261019_290000_package_files::Migration),
         ]
     }
 }
@@ -99,8 +99,8 @@ async fn receive_delivery(
     let subject: String;
 
     match build_result.success {
-        true => subject = format!("Updated {}", build_result.task.name),
-        false => subject = format!("Failed to update {}", build_result.task.name),
+        true => subject = format!("Updated {}", build_result.task.display_name()),
+        false => subject = format!("Failed to update {}", build_result.task.display_name()),
     }
 
     let email = Message::builder()
@@ -0,0 +1,98 @@
+use crate::RepoError;
+use std::io::{Read, Write};
+
+/// Compression of the generated database archives
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Compression {
+    #[default]
+    Gzip,
+    Zstd,
+    Xz,
+}
+
+impl Compression {
+    /// File extension after `.tar`
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gz",
+            Compression::Zstd => "zst",
+            Compression::Xz => "xz",
+        }
+    }
+
+    fn from_filename(filename: &str) -> Option<Compression> {
+        if filename.ends_with(".gz") {
+            Some(Compression::Gzip)
+        } else if filename.ends_with(".zst") {
+            Some(Compression::Zstd)
+        } else if filename.ends_with(".xz") {
+            Some(Compression::Xz)
+        } else {
+            None
+        }
+    }
+
+    /// Wraps a writer, the returned writer has to be finished with [`Encoder::finish`]
+    pub(crate) fn encoder<W: Write>(&self, writer: W) -> Result<Encoder<W>, RepoError> {
+        Ok(match self {
+            Compression::Gzip => Encoder::Gzip(flate2::write::GzEncoder::new(
+                writer,
+                flate2::Compression::default(),
+            )),
+            Compression::Zstd => Encoder::Zstd(zstd::Encoder::new(writer, 0)?),
+            Compression::Xz => Encoder::Xz(xz2::write::XzEncoder::new(writer, 6)),
+        })
+    }
+}
+
+pub(crate) enum Encoder<W: Write> {
+    Gzip(flate2::write::GzEncoder<W>),
+    Zstd(zstd::Encoder<'static, W>),
+    Xz(xz2::write::XzEncoder<W>),
+}
+
+impl<W: Write> Encoder<W> {
+    pub(crate) fn finish(self) -> std::io::Result<W> {
+        match self {
+            Encoder::Gzip(e) => e.finish(),
+            Encoder::Zstd(e) => e.finish(),
+            Encoder::Xz(e) => e.finish(),
+        }
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Encoder::Gzip(e) => e.write(buf),
+            Encoder::Zstd(e) => e.write(buf),
+            Encoder::Xz(e) => e.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Encoder::Gzip(e) => e.flush(),
+            Encoder::Zstd(e) => e.flush(),
+            Encoder::Xz(e) => e.flush(),
+        }
+    }
+}
+
+/// Wraps a reader of a (possibly compressed) tar archive, the compression is detected from the file name
+pub(crate) fn decompress<'a, R: Read + 'a>(
+    reader: R,
+    filename: &str,
+) -> Result<Box<dyn Read + 'a>, RepoError> {
+    Ok(match Compression::from_filename(filename) {
+        Some(Compression::Gzip) => Box::new(flate2::read::GzDecoder::new(reader)),
+        Some(Compression::Zstd) => Box::new(zstd::Decoder::new(reader)?),
+        Some(Compression::Xz) => Box::new(xz2::read::XzDecoder::new(reader)),
+        None if filename.ends_with(".tar") => Box::new(reader),
+        None => {
+            return Err(RepoError::InvalidPackage(format!(
+                "unsupported compression of {filename}"
+            )));
+        }
+    })
+}
@@ -0,0 +1,226 @@
+use std::fmt;
+
+/// Order of the sections in a `desc` file, as written by `repo-add`
+const SECTION_ORDER: [&str; 22] = [
+    "FILENAME",
+    "NAME",
+    "BASE",
+    "VERSION",
+    "DESC",
+    "GROUPS",
+    "CSIZE",
+    "ISIZE",
+    "SHA256SUM",
+    "PGPSIG",
+    "URL",
+    "LICENSE",
+    "ARCH",
+    "BUILDDATE",
+    "PACKAGER",
+    "REPLACES",
+    "CONFLICTS",
+    "PROVIDES",
+    "DEPENDS",
+    "OPTDEPENDS",
+    "MAKEDEPENDS",
+    "CHECKDEPENDS",
+];
+
+/// Maps the keys of a `.PKGINFO` file to the sections of a `desc` file
+const PKGINFO_SECTIONS: [(&str, &str); 18] = [
+    ("pkgname", "NAME"),
+    ("pkgbase", "BASE"),
+    ("pkgver", "VERSION"),
+    ("pkgdesc", "DESC"),
+    ("group", "GROUPS"),
+    ("size", "ISIZE"),
+    ("url", "URL"),
+    ("license", "LICENSE"),
+    ("arch", "ARCH"),
+    ("builddate", "BUILDDATE"),
+    ("packager", "PACKAGER"),
+    ("replaces", "REPLACES"),
+    ("conflict", "CONFLICTS"),
+    ("provides", "PROVIDES"),
+    ("depend", "DEPENDS"),
+    ("optdepend", "OPTDEPENDS"),
+    ("makedepend", "MAKEDEPENDS"),
+    ("checkdepend", "CHECKDEPENDS"),
+];
+
+/// The `desc` entry of a package in a repository database.
+///
+/// It consists of `%SECTION%` headers, each followed by one value per line and an empty line.
+///
+/// # Example
+///
+/// ```
+/// use repo_db::desc::Desc;
+/// let content = "%NAME%\nfoo\n\n%VERSION%\n1.0-1\n\n%DEPENDS%\nglibc\nbar>=2\n\n";
+/// let desc = Desc::parse(content);
+/// assert_eq!(desc.entry_name(), "foo-1.0-1");
+/// assert_eq!(desc.get("DEPENDS").unwrap(), ["glibc", "bar>=2"]);
+/// assert_eq!(desc.to_string(), content);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Desc {
+    sections: Vec<(String, Vec<String>)>,
+}
+
+impl Desc {
+    /// Parses the content of a `desc` file
+    pub fn parse(content: &str) -> Desc {
+        let mut desc = Desc::default();
+        for line in content.lines() {
+            if let Some(section) = line.strip_prefix('%').and_then(|l| l.strip_suffix('%')) {
+                desc.sections.push((section.to_string(), Vec::new()));
+            } else if !line.is_empty()
+                && let Some((_, values)) = desc.sections.last_mut()
+            {
+                values.push(line.to_string());
+            }
+        }
+        desc
+    }
+
+    /// Creates the entry of a package from its `.PKGINFO` file and the data of the package file.
+    ///
+    /// The sections are ordered like `repo-add` writes them, empty ones are left out.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use repo_db::desc::Desc;
+    /// let pkginfo = "# Generated by makepkg\n\
+    ///     pkgname = foo\n\
+    ///     pkgver = 1:1.0-1\n\
+    ///     pkgdesc = A package\n\
+    ///     depend = glibc\n\
+    ///     depend = bar>=2\n\
+    ///     arch = x86_64\n\
+    ///     size = 1024\n";
+    /// let desc = Desc::from_pkginfo(pkginfo, "foo-1:1.0-1-x86_64.pkg.tar.zst", 512, "ab12", None);
+    /// assert_eq!(
+    ///     desc.to_string(),
+    ///     "%FILENAME%\nfoo-1:1.0-1-x86_64.pkg.tar.zst\n\n\
+    ///      %NAME%\nfoo\n\n\
+    ///      %BASE%\nfoo\n\n\
+    ///      %VERSION%\n1:1.0-1\n\n\
+    ///      %DESC%\nA package\n\n\
+    ///      %CSIZE%\n512\n\n\
+    ///      %ISIZE%\n1024\n\n\
+    ///      %SHA256SUM%\nab12\n\n\
+    ///      %ARCH%\nx86_64\n\n\
+    ///      %DEPENDS%\nglibc\nbar>=2\n\n"
+    /// );
+    /// ```
+    pub fn from_pkginfo(
+        pkginfo: &str,
+        filename: &str,
+        csize: u64,
+        sha256: &str,
+        pgpsig: Option<String>,
+    ) -> Desc {
+        let mut desc = Desc::default();
+        desc.set("FILENAME", vec![filename.to_string()]);
+        desc.set("CSIZE", vec![csize.to_string()]);
+        desc.set("SHA256SUM", vec![sha256.to_string()]);
+        if let Some(pgpsig) = pgpsig {
+            desc.set("PGPSIG", vec![pgpsig]);
+        }
+
+        for line in pkginfo.lines() {
+            if line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once(" = ") else {
+                continue;
+            };
+            if let Some((_, section)) = PKGINFO_SECTIONS.iter().find(|(k, _)| *k == key) {
+                desc.push(section, value.to_string());
+            }
+        }
+
+        // fall back to the package name like `repo-add` does
+        if desc.first("BASE").is_none()
+            && let Some(name) = desc.first("NAME").map(str::to_string)
+        {
+            desc.set("BASE", vec![name]);
+        }
+        desc.sort();
+        desc
+    }
+
+    /// Returns all values of a section
+    pub fn get(&self, section: &str) -> Option<&[String]> {
+        self.sections
+            .iter()
+            .find(|(s, _)| s == section)
+            .map(|(_, values)| values.as_slice())
+    }
+
+    /// Returns the first value of a section
+    pub fn first(&self, section: &str) -> Option<&str> {
+        self.get(section)
+            .and_then(|values| values.first())
+            .map(String::as_str)
+    }
+
+    /// Replaces the values of a section, adding it if it's missing
+    pub fn set(&mut self, section: &str, values: Vec<String>) {
+        match self.sections.iter_mut().find(|(s, _)| s == section) {
+            Some((_, existing)) => *existing = values,
+            None => self.sections.push((section.to_string(), values)),
+        }
+    }
+
+    fn push(&mut self, section: &str, value: String) {
+        match self.sections.iter_mut().find(|(s, _)| s == section) {
+            Some((_, existing)) => existing.push(value),
+            None => self.sections.push((section.to_string(), vec![value])),
+        }
+    }
+
+    /// Orders the sections like `repo-add`, unknown sections are kept at the end
+    fn sort(&mut self) {
+        self.sections.sort_by_key(|(section, _)| {
+            SECTION_ORDER
+                .iter()
+                .position(|s| s == section)
+                .unwrap_or(SECTION_ORDER.len())
+        });
+    }
+
+    pub fn name(&self) -> &str {
+        self.first("NAME").unwrap_or_default()
+    }
+
+    pub fn version(&self) -> &str {
+        self.first("VERSION").unwrap_or_default()
+    }
+
+    pub fn filename(&self) -> &str {
+        self.first("FILENAME").unwrap_or_default()
+    }
+
+    /// Name of the entry's directory in the database, `<name>-<version>`
+    pub fn entry_name(&self) -> String {
+        format!("{}-{}", self.name(), self.version())
+    }
+}
+
+impl fmt::Display for Desc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (section, values) in &self.sections {
+            if values.is_empty() {
+                continue;
+            }
+            writeln!(f, "%{section}%")?;
+            for value in values {
+                writeln!(f, "{value}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,497 @@
+//! Generates and updates pacman repository databases (`<repo>.db.tar.*` and `<repo>.files.tar.*`)
+//! without `repo-add`.
+
+pub mod archive;
+pub mod desc;
+pub mod package;
+pub mod version;
+
+use crate::archive::{Compression, decompress};
+use crate::desc::Desc;
+use crate::package::{Package, is_package_file, parse_package_filename};
+use crate::version::vercmp;
+use log::{info, warn};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread::sleep;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How long to wait for another process to finish updating the database
+const LOCK_TIMEOUT: Duration = Duration::from_secs(60);
+const LOCK_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+#[derive(Debug)]
+pub enum RepoError {
+    Io(std::io::Error),
+    InvalidPackage(String),
+    /// The lock file of the database exists for longer than [`LOCK_TIMEOUT`]
+    Locked(PathBuf),
+    Signing(String),
+}
+
+impl fmt::Display for RepoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RepoError::Io(e) => write!(f, "{e}"),
+            RepoError::InvalidPackage(e) => write!(f, "invalid package: {e}"),
+            RepoError::Locked(path) => write!(f, "repository is locked by {}", path.display()),
+            RepoError::Signing(e) => write!(f, "failed to sign the database: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for RepoError {}
+
+impl From<std::io::Error> for RepoError {
+    fn from(e: std::io::Error) -> Self {
+        RepoError::Io(e)
+    }
+}
+
+/// Calculates the hex encoded SHA-256 checksum of a file without loading it into memory
+pub fn file_sha256(path: &Path) -> std::io::Result<String> {
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect())
+}
+
+/// Returns whether a file name is one of the databases of repository `repo` or their signatures.
+///
+/// These are `<repo>.db` and `<repo>.files`, the archives they link to and the `.sig` files of both.
+///
+/// # Example
+///
+/// ```
+/// use repo_db::is_database_file;
+/// assert!(is_database_file("aurbuilder.db", "aurbuilder"));
+/// assert!(is_database_file("aurbuilder.files.tar.zst", "aurbuilder"));
+/// assert!(is_database_file("aurbuilder.db.tar.gz.sig", "aurbuilder"));
+/// assert!(is_database_file("aurbuilder.files.sig", "aurbuilder"));
+/// // packages whose names contain `.db` or `.files` are no databases
+/// assert!(!is_database_file("python-dbus.db-1.0-1-any.pkg.tar.zst", "aurbuilder"));
+/// assert!(!is_database_file("libfoo.files-2.0-1-x86_64.pkg.tar.zst", "aurbuilder"));
+/// assert!(!is_database_file("other.db", "aurbuilder"));
+/// assert!(!is_database_file("aurbuilder.db.tar.gz.old", "aurbuilder"));
+/// ```
+pub fn is_database_file(filename: &str, repo: &str) -> bool {
+    let filename = filename.strip_suffix(".sig").unwrap_or(filename);
+    let Some(rest) = filename.strip_prefix(repo).and_then(|r| r.strip_prefix('.')) else {
+        return false;
+    };
+    let Some(kind) = rest.strip_prefix("db").or_else(|| rest.strip_prefix("files")) else {
+        return false;
+    };
+    match kind {
+        "" | ".tar" => true,
+        _ => kind
+            .strip_prefix(".tar.")
+            .is_some_and(|ext| !ext.is_empty() && ext.chars().all(|c| c.is_ascii_alphanumeric())),
+    }
+}
+
+/// Creates detached signatures of the written databases
+pub trait Signer: Send + Sync {
+    /// Writes the detached signature of `file` to `signature`
+    fn sign(&self, file: &Path, signature: &Path) -> Result<(), String>;
+}
+
+/// A package in the repository database
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub desc: Desc,
+    /// Only available if the files database exists
+    pub files: Vec<String>,
+}
+
+/// Removes the lock file when dropped
+struct Lock(PathBuf);
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// A pacman repository in a local directory, containing the package files and databases.
+///
+/// # Example
+///
+/// ```no_run
+/// use repo_db::Repository;
+/// use std::path::PathBuf;
+/// let repo = Repository::new("/srv/repo/aurbuilder/x86_64", "aurbuilder").keep_versions(2);
+/// repo.add(&[PathBuf::from("/srv/repo/aurbuilder/x86_64/foo-1.0-1-x86_64.pkg.tar.zst")]).unwrap();
+/// ```
+#[derive(Clone)]
+pub struct Repository {
+    dir: PathBuf,
+    name: String,
+    compression: Compression,
+    keep_versions: usize,
+    signer: Option<Arc<dyn Signer>>,
+}
+
+impl Repository {
+    pub fn new(dir: impl Into<PathBuf>, name: &str) -> Repository {
+        Repository {
+            dir: dir.into(),
+            name: name.to_string(),
+            compression: Compression::default(),
+            keep_versions: 0,
+            signer: None,
+        }
+    }
+
+    /// Signs the databases with every update, the signatures are written to `<database>.sig`
+    pub fn signer(mut self, signer: Arc<dyn Signer>) -> Repository {
+        self.signer = Some(signer);
+        self
+    }
+
+    /// Sets the compression of newly written databases
+    pub fn compression(mut self, compression: Compression) -> Repository {
+        self.compression = compression;
+        self
+    }
+
+    /// Sets how many old package files are kept next to the current one when a package is updated
+    pub fn keep_versions(mut self, keep_versions: usize) -> Repository {
+        self.keep_versions = keep_versions;
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Path of the package database, `<dir>/<name>.db.tar.<ext>`
+    pub fn db_path(&self) -> PathBuf {
+        self.dir
+            .join(format!("{}.db.tar.{}", self.name, self.compression.extension()))
+    }
+
+    /// Path of the files database, `<dir>/<name>.files.tar.<ext>`
+    pub fn files_path(&self) -> PathBuf {
+        self.dir
+            .join(format!("{}.files.tar.{}", self.name, self.compression.extension()))
+    }
+
+    /// Reads all packages of the repository, keyed by their name.
+    ///
+    /// The files database is preferred, as it contains the file lists as well.
+    /// Databases written with another compression are found through the `<name>.files` and `<name>.db` links.
+    pub fn entries(&self) -> Result<BTreeMap<String, Entry>, RepoError> {
+        let links = ["files", "db"].map(|kind| self.dir.join(format!("{}.{kind}", self.name)));
+        for path in [self.files_path(), self.db_path()] {
+            if path.exists() {
+                return read_database(&path);
+            }
+        }
+        for link in links {
+            if let Ok(path) = fs::canonicalize(link) {
+                return read_database(&path);
+            }
+        }
+        Ok(BTreeMap::new())
+    }
+
+    /// Adds package files in the repository directory to the databases
+    pub fn add(&self, packages: &[PathBuf]) -> Result<(), RepoError> {
+        self.update(packages, &[])
+    }
+
+    /// Removes packages from the databases, the package files are kept
+    pub fn remove(&self, names: &[String]) -> Result<(), RepoError> {
+        self.update(&[], names)
+    }
+
+    /// Adds and removes packages in a single update of the databases.
+    ///
+    /// The databases are written to temporary files and renamed, so readers see either the old or the new state.
+    /// Older versions of the added packages beyond [`Repository::keep_versions`] are deleted.
+    pub fn update(&self, add: &[PathBuf], remove: &[String]) -> Result<(), RepoError> {
+        let packages = add
+            .iter()
+            .map(|path| Package::read(path))
+            .collect::<Result<Vec<Package>, RepoError>>()?;
+
+        let _lock = self.lock()?;
+        let mut entries = self.entries()?;
+        for name in remove {
+            if entries.remove(name).is_none() {
+                warn!("Package {name} is not in repository {}", self.name);
+            }
+        }
+        for package in &packages {
+            info!(
+                "Adding {} {} to repository {}",
+                package.desc.name(),
+                package.desc.version(),
+                self.name
+            );
+            entries.insert(
+                package.desc.name().to_string(),
+                Entry {
+                    desc: package.desc.clone(),
+                    files: package.files.clone(),
+                },
+            );
+        }
+
+        self.write_database(&self.db_path(), &entries, false)?;
+        self.write_database(&self.files_path(), &entries, true)?;
+        self.link(&self.db_path(), "db")?;
+        self.link(&self.files_path(), "files")?;
+        if self.signer.is_some() {
+            self.link(&sig_path(&self.db_path()), "db.sig")?;
+            self.link(&sig_path(&self.files_path()), "files.sig")?;
+        }
+
+        for package in &packages {
+            self.prune(package.desc.name(), package.desc.filename())?;
+        }
+        Ok(())
+    }
+
+    /// Creates `<name>.db.lck` containing the owner of the lock, see [`lock_owner`].
+    ///
+    /// A lock left behind by a process of this host that doesn't run anymore is removed.
+    fn lock(&self) -> Result<Lock, RepoError> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(format!("{}.db.lck", self.name));
+        let started = SystemTime::now();
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    let lock = Lock(path);
+                    file.write_all(lock_owner().as_bytes())?;
+                    return Ok(lock);
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    if is_stale_lock(&path) {
+                        warn!("Removing stale lock {}", path.display());
+                        let _ = fs::remove_file(&path);
+                        continue;
+                    }
+                    if started.elapsed().unwrap_or_default() > LOCK_TIMEOUT {
+                        return Err(RepoError::Locked(path));
+                    }
+                    sleep(LOCK_RETRY_DELAY);
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    fn write_database(
+        &self,
+        path: &Path,
+        entries: &BTreeMap<String, Entry>,
+        with_files: bool,
+    ) -> Result<(), RepoError> {
+        let filename = path
+            .file_name()
+            .map(|f| f.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let partial = path.with_file_name(format!(".{filename}.part"));
+        let mtime = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let mut builder = tar::Builder::new(self.compression.encoder(File::create(&partial)?)?);
+        for entry in entries.values() {
+            let dir = entry.desc.entry_name();
+            append_dir(&mut builder, &dir, mtime)?;
+            append_file(
+                &mut builder,
+                &format!("{dir}/desc"),
+                entry.desc.to_string().as_bytes(),
+                mtime,
+            )?;
+            if with_files {
+                let mut files = String::from("%FILES%\n");
+                for file in &entry.files {
+                    files.push_str(file);
+                    files.push('\n');
+                }
+                append_file(&mut builder, &format!("{dir}/files"), files.as_bytes(), mtime)?;
+            }
+        }
+        builder.into_inner()?.finish()?.sync_all()?;
+
+        let signature = sig_path(path);
+        let partial_signature = path.with_file_name(format!(".{filename}.sig.part"));
+        if let Some(signer) = &self.signer
+            && let Err(e) = signer.sign(&partial, &partial_signature)
+        {
+            let _ = fs::remove_file(&partial);
+            return Err(RepoError::Signing(e));
+        }
+        // removed first, so the new database is never paired with the old signature
+        match fs::remove_file(&signature) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+        fs::rename(&partial, path)?;
+        if self.signer.is_some() {
+            fs::rename(&partial_signature, signature)?;
+        }
+        Ok(())
+    }
+
+    /// Points `<name>.<kind>` to the database, like `repo-add` does
+    fn link(&self, target: &Path, kind: &str) -> Result<(), RepoError> {
+        let Some(target) = target.file_name() else {
+            return Ok(());
+        };
+        let link = self.dir.join(format!("{}.{kind}", self.name));
+        let partial = self.dir.join(format!(".{}.{kind}.part", self.name));
+        let _ = fs::remove_file(&partial);
+        std::os::unix::fs::symlink(target, &partial)?;
+        fs::rename(&partial, link)?;
+        Ok(())
+    }
+
+    /// Deletes old package files of `name`, keeping the current one and the newest [`Repository::keep_versions`]
+    fn prune(&self, name: &str, current: &str) -> Result<(), RepoError> {
+        let mut old_versions: Vec<(String, String)> = fs::read_dir(&self.dir)?
+            .flatten()
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .filter(|f| is_package_file(f) && f != current)
+            .filter_map(|f| {
+                let (pkgname, version, _) = parse_package_filename(&f)?;
+                (pkgname == name).then_some((version, f.clone()))
+            })
+            .collect();
+        old_versions.sort_by(|(a, _), (b, _)| vercmp(b, a));
+
+        for (_, file) in old_versions.into_iter().skip(self.keep_versions) {
+            info!("Deleting old package file {file} from repository {}", self.name);
+            fs::remove_file(self.dir.join(&file))?;
+            let _ = fs::remove_file(self.dir.join(format!("{file}.sig")));
+        }
+        Ok(())
+    }
+}
+
+/// Identifies the process holding a lock as `<pid> <hostname>`.
+///
+/// The hostname tells apart processes of different containers sharing the repository directory.
+fn lock_owner() -> String {
+    format!("{} {}", std::process::id(), hostname())
+}
+
+fn hostname() -> String {
+    fs::read_to_string("/proc/sys/kernel/hostname")
+        .map(|h| h.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Returns whether a lock was taken by a process of this host that has exited.
+///
+/// Locks of other hosts and ones without an owner, e.g. while it is still being written, are never stale.
+fn is_stale_lock(path: &Path) -> bool {
+    let Ok(owner) = fs::read_to_string(path) else {
+        return false;
+    };
+    let Some((pid, host)) = owner.split_once(' ') else {
+        return false;
+    };
+    let Ok(pid) = pid.parse::<u32>() else {
+        return false;
+    };
+    let own_host = hostname();
+    !own_host.is_empty() && host == own_host && !Path::new(&format!("/proc/{pid}")).exists()
+}
+
+fn sig_path(path: &Path) -> PathBuf {
+    let mut signature = path.as_os_str().to_owned();
+    signature.push(".sig");
+    PathBuf::from(signature)
+}
+
+fn append_dir<W: Write>(
+    builder: &mut tar::Builder<W>,
+    path: &str,
+    mtime: u64,
+) -> Result<(), RepoError> {
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Directory);
+    header.set_mode(0o755);
+    header.set_mtime(mtime);
+    header.set_size(0);
+    builder.append_data(&mut header, format!("{path}/"), std::io::empty())?;
+    Ok(())
+}
+
+fn append_file<W: Write>(
+    builder: &mut tar::Builder<W>,
+    path: &str,
+    content: &[u8],
+    mtime: u64,
+) -> Result<(), RepoError> {
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Regular);
+    header.set_mode(0o644);
+    header.set_mtime(mtime);
+    header.set_size(content.len() as u64);
+    builder.append_data(&mut header, path, content)?;
+    Ok(())
+}
+
+/// Reads the entries of a database archive, keyed by the package name
+fn read_database(path: &Path) -> Result<BTreeMap<String, Entry>, RepoError> {
+    let filename = path
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut archive = tar::Archive::new(decompress(BufReader::new(File::open(path)?), &filename)?);
+
+    // entries are grouped by their `<name>-<version>` directory
+    let mut by_dir: BTreeMap<String, (Option<Desc>, Vec<String>)> = BTreeMap::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let entry_path = entry.path()?.to_string_lossy().to_string();
+        let Some((dir, file)) = entry_path.split_once('/') else {
+            continue;
+        };
+        let dir = dir.to_string();
+        let file = file.to_string();
+        let mut content = String::new();
+        entry.read_to_string(&mut content)?;
+        let (desc, files) = by_dir.entry(dir).or_default();
+        match file.as_str() {
+            "desc" => *desc = Some(Desc::parse(&content)),
+            "files" => {
+                *files = content
+                    .lines()
+                    .filter(|l| !l.is_empty() && *l != "%FILES%")
+                    .map(str::to_string)
+                    .collect()
+            }
+            _ => {}
+        }
+    }
+
+    Ok(by_dir
+        .into_values()
+        .filter_map(|(desc, files)| {
+            let desc = desc?;
+            Some((desc.name().to_string(), Entry { desc, files }))
+        })
+        .collect())
+}
@@ -0,0 +1,101 @@
+use crate::archive::decompress;
+use crate::desc::Desc;
+use crate::{RepoError, file_sha256};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+/// A package file read for adding it to a repository
+#[derive(Debug, Clone)]
+pub struct Package {
+    pub desc: Desc,
+    /// Paths installed by the package, directories end with `/`
+    pub files: Vec<String>,
+    /// Content of the `.BUILDINFO`, missing in packages built without one
+    pub buildinfo: Option<String>,
+}
+
+impl Package {
+    /// Reads the `.PKGINFO`, the `.BUILDINFO` and the file list of a package file.
+    ///
+    /// A detached signature next to the package (`<file>.sig`) is added to the entry.
+    pub fn read(path: &Path) -> Result<Package, RepoError> {
+        let filename = path
+            .file_name()
+            .map(|f| f.to_string_lossy().to_string())
+            .ok_or_else(|| RepoError::InvalidPackage(path.display().to_string()))?;
+
+        let mut archive = tar::Archive::new(decompress(
+            BufReader::new(File::open(path)?),
+            &filename,
+        )?);
+        let mut pkginfo = None;
+        let mut buildinfo = None;
+        let mut files = Vec::new();
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let entry_path = entry.path()?.to_string_lossy().to_string();
+            let entry_path = entry_path.trim_start_matches("./").to_string();
+            if entry_path == ".PKGINFO" {
+                let mut content = String::new();
+                entry.read_to_string(&mut content)?;
+                pkginfo = Some(content);
+            } else if entry_path == ".BUILDINFO" {
+                let mut content = String::new();
+                entry.read_to_string(&mut content)?;
+                buildinfo = Some(content);
+            } else if !entry_path.starts_with('.') {
+                if entry.header().entry_type().is_dir() && !entry_path.ends_with('/') {
+                    files.push(format!("{entry_path}/"));
+                } else {
+                    files.push(entry_path);
+                }
+            }
+        }
+        files.sort();
+        files.dedup();
+
+        let pkginfo = pkginfo
+            .ok_or_else(|| RepoError::InvalidPackage(format!("{filename} has no .PKGINFO")))?;
+        let pgpsig = match fs::read(path.with_file_name(format!("{filename}.sig"))) {
+            Ok(signature) => Some(STANDARD.encode(signature)),
+            Err(_) => None,
+        };
+        let desc = Desc::from_pkginfo(
+            &pkginfo,
+            &filename,
+            fs::metadata(path)?.len(),
+            &file_sha256(path)?,
+            pgpsig,
+        );
+        if desc.name().is_empty() || desc.version().is_empty() {
+            return Err(RepoError::InvalidPackage(format!(
+                "{filename} has no name or version"
+            )));
+        }
+
+        Ok(Package {
+            desc,
+            files,
+            buildinfo,
+        })
+    }
+}
+
+/// Returns whether a file name looks like a package file
+pub fn is_package_file(filename: &str) -> bool {
+    filename.contains(".pkg.tar") && !filename.ends_with(".sig")
+}
+
+/// Splits a package file name `<name>-<pkgver>-<pkgrel>-<arch>.pkg.tar.<ext>` into name, version and arch
+pub fn parse_package_filename(filename: &str) -> Option<(&str, String, &str)> {
+    let stem = &filename[..filename.find(".pkg.tar")?];
+    let mut parts = stem.rsplitn(4, '-');
+    let arch = parts.next()?;
+    let pkgrel = parts.next()?;
+    let pkgver = parts.next()?;
+    let name = parts.next()?;
+    Some((name, format!("{pkgver}-{pkgrel}"), arch))
+}
@@ -0,0 +1,152 @@
+use std::cmp::Ordering;
+
+/// Compares two version segments the way `rpmvercmp` of libalpm does
+fn rpmvercmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+    let a = a.as_bytes();
+    let b = b.as_bytes();
+    // `one`/`two` point to the start of the current segment, `ptr1`/`ptr2` to its end
+    let (mut one, mut two) = (0, 0);
+    let (mut ptr1, mut ptr2) = (0, 0);
+
+    while one < a.len() && two < b.len() {
+        while one < a.len() && !a[one].is_ascii_alphanumeric() {
+            one += 1;
+        }
+        while two < b.len() && !b[two].is_ascii_alphanumeric() {
+            two += 1;
+        }
+        if one >= a.len() || two >= b.len() {
+            break;
+        }
+
+        // different separator lengths decide the comparison
+        if one - ptr1 != two - ptr2 {
+            return (one - ptr1).cmp(&(two - ptr2));
+        }
+
+        ptr1 = one;
+        ptr2 = two;
+        let is_num = a[ptr1].is_ascii_digit();
+        if is_num {
+            while ptr1 < a.len() && a[ptr1].is_ascii_digit() {
+                ptr1 += 1;
+            }
+            while ptr2 < b.len() && b[ptr2].is_ascii_digit() {
+                ptr2 += 1;
+            }
+        } else {
+            while ptr1 < a.len() && a[ptr1].is_ascii_alphabetic() {
+                ptr1 += 1;
+            }
+            while ptr2 < b.len() && b[ptr2].is_ascii_alphabetic() {
+                ptr2 += 1;
+            }
+        }
+
+        // segments of different types, numbers are newer
+        if two == ptr2 {
+            return if is_num {
+                Ordering::Greater
+            } else {
+                Ordering::Less
+            };
+        }
+
+        let mut segment1 = &a[one..ptr1];
+        let mut segment2 = &b[two..ptr2];
+        if is_num {
+            while segment1.first() == Some(&b'0') {
+                segment1 = &segment1[1..];
+            }
+            while segment2.first() == Some(&b'0') {
+                segment2 = &segment2[1..];
+            }
+            if segment1.len() != segment2.len() {
+                return segment1.len().cmp(&segment2.len());
+            }
+        }
+        match segment1.cmp(segment2) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+
+        one = ptr1;
+        two = ptr2;
+    }
+
+    let rest1 = &a[one..];
+    let rest2 = &b[two..];
+    if rest1.is_empty() && rest2.is_empty() {
+        return Ordering::Equal;
+    }
+    // a remaining alpha segment never beats an empty string
+    let first_alpha = |s: &[u8]| s.first().is_some_and(|c| c.is_ascii_alphabetic());
+    if (rest1.is_empty() && !first_alpha(rest2)) || first_alpha(rest1) {
+        Ordering::Less
+    } else {
+        Ordering::Greater
+    }
+}
+
+/// Splits `[epoch:]pkgver[-pkgrel]` into its parts, the epoch defaults to `0`
+fn parse_evr(version: &str) -> (&str, &str, Option<&str>) {
+    let digits = version
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(version.len());
+    let (epoch, rest) = match version[digits..].strip_prefix(':') {
+        Some(rest) if digits > 0 => (&version[..digits], rest),
+        Some(rest) => ("0", rest),
+        None => ("0", version),
+    };
+    match rest.rsplit_once('-') {
+        Some((pkgver, pkgrel)) => (epoch, pkgver, Some(pkgrel)),
+        None => (epoch, rest, None),
+    }
+}
+
+/// Compares two package versions like `vercmp` of pacman does.
+///
+/// # Example
+///
+/// ```
+/// use repo_db::version::vercmp;
+/// use std::cmp::Ordering::{Equal, Greater, Less};
+/// assert_eq!(vercmp("1.0rc1-1", "1.0-1"), Less);
+///
+/// // the epoch outweighs the version, a missing one is 0
+/// assert_eq!(vercmp("1:0.9-1", "2.0-1"), Greater);
+/// assert_eq!(vercmp("0:1.0-1", "1.0-1"), Equal);
+/// assert_eq!(vercmp("2:1.0-1", "10:1.0-1"), Less);
+///
+/// // a trailing letter is a pre-release, a trailing number segment a newer version
+/// assert_eq!(vercmp("1.0a", "1.0"), Less);
+/// assert_eq!(vercmp("1.0.a", "1.0"), Greater);
+/// assert_eq!(vercmp("1.0.0", "1.0"), Greater);
+///
+/// // alphanumeric segments: letters compare as strings, numbers numerically and are newer than letters
+/// assert_eq!(vercmp("1.0alpha", "1.0beta"), Less);
+/// assert_eq!(vercmp("1.0.1", "1.0.a"), Greater);
+/// assert_eq!(vercmp("1.10", "1.9"), Greater);
+/// assert_eq!(vercmp("1.01", "1.1"), Equal);
+///
+/// // the pkgrel is only compared if both versions have one
+/// assert_eq!(vercmp("1.0-10", "1.0-9"), Greater);
+/// assert_eq!(vercmp("1.0-1", "1.0-1.1"), Less);
+/// assert_eq!(vercmp("1.0-2", "1.0"), Equal);
+/// ```
+pub fn vercmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+    let (epoch1, pkgver1, pkgrel1) = parse_evr(a);
+    let (epoch2, pkgver2, pkgrel2) = parse_evr(b);
+    rpmvercmp(epoch1, epoch2)
+        .then_with(|| rpmvercmp(pkgver1, pkgver2))
+        .then_with(|| match (pkgrel1, pkgrel2) {
+            (Some(rel1), Some(rel2)) => rpmvercmp(rel1, rel2),
+            _ => Ordering::Equal,
+        })
+}
@@ -2,18 +2,131 @@ mod setup_rabbit_mq;
 mod package_checkers;
 
 use std::process::exit;
-use database::connect_to_db;
+use database::{connect_to_db, Database};
+use database::entities::package_metadata;
 use common::environment::{get_environment_variable, load_dotenv, VERSION};
-use common::types::BuildTaskTransmissionFormat;
+use common::types::{
+    is_valid_variant_name, BuildTaskTransmissionFormat, PackageSearchResult, PackageVariant, VerificationRequest,
+};
 use lapin::options::BasicPublishOptions;
-use lapin::BasicProperties;
+use lapin::{BasicProperties, Channel};
 use log::{debug, error, info};
 use std::time::Duration;
 use tokio::time::sleep;
 use common::config::{Configurable, ServerConfig};
+use common::routing::{build_routing_key, declare_build_queue, BUILD_EXCHANGE};
 
 use package_checkers::{*};
 
+/// Default delay between a build and the check that it's reproducible
+const REPRODUCIBILITY_DELAY: u64 = 60 * 60 * 24;
+
+fn create_task(
+    package: &package_metadata::Model,
+    data: &PackageSearchResult,
+    distcc_hosts: Option<Vec<String>>,
+) -> BuildTaskTransmissionFormat {
+    BuildTaskTransmissionFormat {
+        id: package.id,
+        name: package.name.clone(),
+        version: data.version.clone(),
+        source: package.source.clone(),
+        subfolder: package.subfolder.clone(),
+        options: data.options.clone(),
+        env: data.environment.clone(),
+        timeout: data.timeout,
+        resources: data.resources.clone(),
+        profile: data.profile.clone(),
+        publish_to: data.publish_to.clone(),
+        variant: data.variant.clone(),
+        cache: data.cache,
+        distcc_hosts,
+        smoke_test: data.smoke_test.clone(),
+        namcap_allow: data.namcap_allow.clone(),
+        verify: None,
+    }
+}
+
+/// Publishes a build task and returns whether the broker confirmed it
+async fn publish_task(
+    build_tx: &Channel,
+    task: &BuildTaskTransmissionFormat,
+    labels: &Option<Vec<String>>,
+) -> lapin::Result<bool> {
+    let routing_key = build_routing_key(&labels.clone().unwrap_or_default());
+    declare_build_queue(build_tx, &routing_key).await?;
+    let confirmation = build_tx
+        .basic_publish(
+            BUILD_EXCHANGE,
+            &routing_key,
+            BasicPublishOptions::default(),
+            serde_json::to_string(task).unwrap().as_ref(),
+            BasicProperties::default(),
+        )
+        .await?
+        .await?;
+    Ok(confirmation.is_ack())
+}
+
+/// Rebuilds the latest build of a package on any suitable worker once it's old enough, to check that it's reproducible
+async fn schedule_verification(db: &Database, build_tx: &Channel, data: &PackageSearchResult, delay: Duration) {
+    let Ok(Some(package)) = db.get_package_by_name_and_variant(&data.name, &data.variant).await else {
+        return;
+    };
+    let build = match db.get_build_to_verify(package.id, delay).await {
+        Ok(Some(build)) => build,
+        Ok(None) => return,
+        Err(e) => {
+            error!("Failed to get the build of {} to verify: {}", data.name, e);
+            return;
+        }
+    };
+    let files = match db.get_published_files(build.id).await {
+        Ok(files) if !files.is_empty() => files,
+        Ok(_) => {
+            debug!("Build {} published no packages, it can't be verified", build.id);
+            return;
+        }
+        Err(e) => {
+            error!("Failed to get the published files of build {}: {}", build.id, e);
+            return;
+        }
+    };
+
+    info!("Verifying that build {} of {} is reproducible", build.id, data.name);
+    let mut task = create_task(&package, data, None);
+    task.version = build.version.clone().unwrap_or(task.version);
+    task.verify = Some(VerificationRequest {
+        build_result_id: build.id,
+        files,
+    });
+    // only marked once the task can't get lost anymore, so a failed publish is retried in the next round
+    match publish_task(build_tx, &task, &data.labels).await {
+        Ok(true) => {}
+        Ok(false) => {
+            error!("The verification task of build {} wasn't confirmed", build.id);
+            return;
+        }
+        Err(e) => {
+            error!("Failed to publish the verification task of build {}: {}", build.id, e);
+            return;
+        }
+    }
+    if let Err(e) = db.set_reproducibility_status(build.id, "pending").await {
+        error!("Failed to mark build {} as pending verification: {}", build.id, e);
+    }
+}
+
+/// Removes the rows and builds of variants of a package that were removed from the config
+async fn remove_stale_variants(db: &Database, name: &str, variants: &Option<Vec<PackageVariant>>) {
+    let names: Vec<String> = variants.iter().flatten().map(|v| v.name.clone()).collect();
+    match db.remove_stale_variants(name, &names).await {
+        Ok(0) => {}
+        Ok(n) => info!("Removed {n} unconfigured variant(s) of {name}"),
+        Err(e) => error!("Failed to remove unconfigured variants of {name}: {}", e),
+    }
+}
+
 #[tokio::main]
 async fn main() {
     load_dotenv().ok();
@@ -29,6 +142,18 @@ async fn main() {
     };
     info!("Starting Aur-Builder Server v{VERSION}");
 
+    let variants = config
+        .aur_packages
+        .iter()
+        .flat_map(|p| p.variants.iter().flatten())
+        .chain(config.git_packages.iter().flat_map(|p| p.variants.iter().flatten()));
+    for variant in variants {
+        if !is_valid_variant_name(&variant.name) {
+            error!("Invalid variant name \"{}\", only A-Z, a-z, 0-9, '_', '.' and '-' are allowed", variant.name);
+            exit(4);
+        }
+    }
+
     let db = connect_to_db().await;
     db.migrate().await;
 
@@ -38,6 +163,20 @@ async fn main() {
     let channels = setup_rabbit_mq::setup_rabbitmq(&db).await;
     let build_tx = channels.build_tx;
 
+    let worker_timeout = Duration::from_secs(config.worker_timeout.unwrap_or(60));
+    let reproducibility_delay = Duration::from_secs(config.reproducibility_delay.unwrap_or(REPRODUCIBILITY_DELAY));
+    let sweeper_db = db.clone();
+    tokio::spawn(async move {
+        loop {
+            match sweeper_db.mark_offline_workers(worker_timeout).await {
+                Ok(0) => {}
+                Ok(n) => info!("{n} worker(s) went offline"),
+                Err(e) => error!("Failed to update worker states: {}", e),
+            }
+            sleep(worker_timeout / 2).await;
+        }
+    });
+
     loop {
         info!("Checking for package updates...");
         let mut package_data = Vec::new();
@@ -47,7 +186,8 @@ async fn main() {
             let aur_result = aur::get_aur_data(pkg).await;
             match aur_result {
                 Ok(aur) => {
-                    package_data.push(aur);
+                    remove_stale_variants(&db, &aur.name, &pkg.variants).await;
+                    package_data.extend(expand_variants(aur, &pkg.variants));
                 }
                 Err(e) => {
                     error!("Failed to get data for aur package \"{}\": {}", pkg.name, e);
@@ -61,7 +201,8 @@ async fn main() {
             let git_result = git::get_git_data(pkg).await;
             match git_result {
                 Ok(git_data) => {
-                    package_data.push(git_data);
+                    remove_stale_variants(&db, &git_data.name, &pkg.variants).await;
+                    package_data.extend(expand_variants(git_data, &pkg.variants));
                 }
                 Err(e) => {
                     error!("Failed to get data for git package \"{}\": {}", pkg.source, e);
@@ -70,32 +211,32 @@ async fn main() {
         }
 
         for data in &package_data {
-            let updated = db.update_metadata(&data).await;
+            let updated = db.update_metadata(data).await;
             // let updated = true;
             if updated {
-                info!("{} was updated!", data.name);
-                let package = db.get_package_by_name(&data.name).await.unwrap().unwrap();
-                let task = BuildTaskTransmissionFormat {
-                    id: package.id.clone(),
-                    name: package.name.clone(),
-                    version: data.version.clone(),
-                    source: package.source.clone(),
-                    subfolder: package.subfolder.clone(),
-                    options: data.options.clone(),
-                    env: data.environment.clone()
-                };
-                build_tx
-                    .basic_publish(
-                        "",
-                        "pkg_build",
-                        BasicPublishOptions::default(),
-                        serde_json::to_string(&task).unwrap().as_ref(),
-                        BasicProperties::default(),
-                    )
+                match &data.variant {
+                    Some(variant) => info!("{} ({variant}) was updated!", data.name),
+                    None => info!("{} was updated!", data.name),
+                }
+                let package = db
+                    .get_package_by_name_and_variant(&data.name, &data.variant)
                     .await
                     .unwrap()
-                    .await
                     .unwrap();
+                let distcc_hosts = match data.distcc {
+                    Some(true) => match db.get_distcc_helpers().await {
+                        Ok(helpers) => Some(helpers.iter().map(|h| h.host_spec()).collect()),
+                        Err(e) => {
+                            error!("Failed to get distcc helpers: {}", e);
+                            None
+                        }
+                    },
+                    _ => None,
+                };
+                let task = create_task(&package, data, distcc_hosts);
+                publish_task(&build_tx, &task, &data.labels).await.unwrap();
+            } else if data.verify_reproducible == Some(true) {
+                schedule_verification(&db, &build_tx, data, reproducibility_delay).await;
             }
         }
         sleep(Duration::from_secs(config.sleepduration.unwrap_or(60*5))).await;
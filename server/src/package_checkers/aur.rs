@@ -61,6 +61,17 @@ pub async fn get_aur_data(package: &AurPackageSettings) -> Result<PackageSearchR
         subfolder: None,
         options: package.options.clone(),
         environment: package.env.clone(),
+        timeout: package.timeout,
+        resources: package.resources.clone(),
+        profile: package.profile.clone(),
+        labels: package.labels.clone(),
+        publish_to: package.publish_to.clone(),
+        variant: None,
+        cache: package.cache,
+        distcc: package.distcc,
+        smoke_test: package.smoke_test.clone(),
+        namcap_allow: package.namcap_allow.clone(),
+        verify_reproducible: package.verify_reproducible,
     };
     
     Ok(result)
@@ -25,6 +25,17 @@ pub async fn get_git_data(pkg: &GitPackageSettings) -> Result<PackageSearchResul
         source: Some(pkg.source.clone()),
         subfolder: pkg.subfolder.clone(),
         options: pkg.options.clone(),
-        environment: pkg.env.clone()
+        environment: pkg.env.clone(),
+        timeout: pkg.timeout,
+        resources: pkg.resources.clone(),
+        profile: pkg.profile.clone(),
+        labels: pkg.labels.clone(),
+        publish_to: pkg.publish_to.clone(),
+        variant: None,
+        cache: pkg.cache,
+        distcc: pkg.distcc,
+        smoke_test: pkg.smoke_test.clone(),
+        namcap_allow: pkg.namcap_allow.clone(),
+        verify_reproducible: pkg.verify_reproducible,
     })
 }
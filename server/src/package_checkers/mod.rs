@@ -1,2 +1,30 @@
 pub mod aur;
-pub mod git;
\ No newline at end of file
+pub mod git;
+
+use common::types::{PackageSearchResult, PackageVariant};
+
+/// Returns one result per variant of a package, or the result itself if it has no variants
+pub fn expand_variants(
+    result: PackageSearchResult,
+    variants: &Option<Vec<PackageVariant>>,
+) -> Vec<PackageSearchResult> {
+    let Some(variants) = variants.as_ref().filter(|v| !v.is_empty()) else {
+        return vec![result];
+    };
+    variants
+        .iter()
+        .map(|variant| {
+            let mut environment = result.environment.clone().unwrap_or_default();
+            environment.extend(variant.env.clone().unwrap_or_default());
+            PackageSearchResult {
+                options: variant.options.clone().or(result.options.clone()),
+                environment: Some(environment).filter(|e| !e.is_empty()),
+                profile: variant.profile.clone().or(result.profile.clone()),
+                labels: variant.labels.clone().or(result.labels.clone()),
+                publish_to: variant.publish_to.clone().or(result.publish_to.clone()),
+                variant: Some(variant.name.clone()),
+                ..result.clone()
+            }
+        })
+        .collect()
+}
@@ -1,25 +1,41 @@
 use common::connect_to_rabbitmq;
-use common::types::BuildResultTransmissionFormat;
+use common::routing::{declare_build_queue, log_stream_name, DEFAULT_BUILD_QUEUE};
+use common::types::{BuildResultTransmissionFormat, WorkerHeartbeatTransmissionFormat};
 use database::Database;
 use futures_util::StreamExt;
 use lapin::{BasicProperties, Channel};
-use lapin::options::{BasicAckOptions, BasicConsumeOptions, BasicPublishOptions, QueueDeclareOptions};
+use lapin::message::Delivery;
+use lapin::options::{BasicAckOptions, BasicConsumeOptions, ConfirmSelectOptions, BasicNackOptions, BasicPublishOptions, QueueDeclareOptions, QueueDeleteOptions};
 use lapin::types::FieldTable;
+use log::error;
 
 pub struct RabbitChannels {
     pub build_tx: Channel
 }
 
+/// Drops a delivery that can't be processed without requeueing it
+async fn reject(delivery: &Delivery) {
+    if let Err(e) = delivery
+        .nack(BasicNackOptions {
+            requeue: false,
+            ..Default::default()
+        })
+        .await
+    {
+        error!("Failed to reject delivery: {}", e);
+    }
+}
+
 pub async fn setup_rabbitmq(db: &Database) -> RabbitChannels {
     let conn = connect_to_rabbitmq().await;
 
     let build_tx = conn.create_channel().await.unwrap();
+    // lets the server tell whether a build task reached its queue
     build_tx
-        .queue_declare(
-            "pkg_build",
-            QueueDeclareOptions::default(),
-            FieldTable::default(),
-        )
+        .confirm_select(ConfirmSelectOptions::default())
+        .await
+        .unwrap();
+    declare_build_queue(&build_tx, DEFAULT_BUILD_QUEUE)
         .await
         .unwrap();
 
@@ -53,23 +69,80 @@ pub async fn setup_rabbitmq(db: &Database) -> RabbitChannels {
         .await
         .unwrap();
 
+    let log_channel = conn.create_channel().await.unwrap();
+
     let locale_db = db.clone();
     tokio::spawn(async move {
         while let Some(delivery) = results_consumer.next().await {
             let delivery = delivery.expect("error in consumer");
-            let data_str = match std::str::from_utf8(&*delivery.data) {
-                Ok(v) => v.to_string(),
-                Err(e) => panic!("Invalid UTF-8 sequence: {}", e),
+            let data: BuildResultTransmissionFormat = match serde_json::from_slice(&delivery.data) {
+                Ok(data) => data,
+                Err(e) => {
+                    error!("Received invalid build result: {}", e);
+                    reject(&delivery).await;
+                    continue;
+                }
             };
-            let data: BuildResultTransmissionFormat = serde_json::from_str(&*data_str).unwrap();
-            locale_db.save_build_results(&data).await.unwrap();
-            notify_tx.basic_publish(
-                "",
-                "notifications",
-                BasicPublishOptions::default(),
-                serde_json::to_string(&data).unwrap().as_ref(),
-                BasicProperties::default(),
-            ).await.unwrap();
+            if let Err(e) = locale_db.save_build_results(&data).await.map_err(|e| e.to_string()) {
+                // redelivering would fail the same way, the message is dead-lettered if the queue has a policy for it
+                error!("Failed to save result of build {}: {}", data.build_id, e);
+                reject(&delivery).await;
+                continue;
+            }
+            // The full log is stored in the database now, the live log isn't needed anymore
+            if let Err(e) = log_channel
+                .queue_delete(&log_stream_name(&data.build_id), QueueDeleteOptions::default())
+                .await
+            {
+                error!("Failed to delete log stream of build {}: {}", data.build_id, e);
+            }
+            // reproducibility checks don't update the package, their outcome is only shown in the web UI
+            if data.task.verify.is_none() {
+                notify_tx.basic_publish(
+                    "",
+                    "notifications",
+                    BasicPublishOptions::default(),
+                    serde_json::to_string(&data).unwrap().as_ref(),
+                    BasicProperties::default(),
+                ).await.unwrap();
+            }
+
+            delivery.ack(BasicAckOptions::default()).await.unwrap();
+        }
+    });
+
+    let heartbeat_channel = conn.create_channel().await.unwrap();
+    heartbeat_channel
+        .queue_declare(
+            "worker_heartbeats",
+            QueueDeclareOptions::default(),
+            FieldTable::default(),
+        )
+        .await
+        .unwrap();
+
+    let mut heartbeat_consumer = heartbeat_channel
+        .basic_consume(
+            "worker_heartbeats",
+            "aur-builder-server-heartbeats",
+            BasicConsumeOptions::default(),
+            FieldTable::default(),
+        )
+        .await
+        .unwrap();
+
+    let heartbeat_db = db.clone();
+    tokio::spawn(async move {
+        while let Some(delivery) = heartbeat_consumer.next().await {
+            let delivery = delivery.expect("error in consumer");
+            match serde_json::from_slice::<WorkerHeartbeatTransmissionFormat>(&delivery.data) {
+                Ok(heartbeat) => {
+                    if let Err(e) = heartbeat_db.save_worker_heartbeat(&heartbeat).await {
+                        error!("Failed to save heartbeat of worker '{}': {}", heartbeat.worker_id, e);
+                    }
+                }
+                Err(e) => error!("Received invalid heartbeat: {}", e),
+            }
 
             delivery.ack(BasicAckOptions::default()).await.unwrap();
         }
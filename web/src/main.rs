@@ -1,17 +1,54 @@
-use axum::extract::Path;
-use axum::response::Html;
+mod repo_files;
+mod repository;
+mod timeline;
+
+use crate::repo_files::{is_servable, list_files, repository_dir, serve_file};
+use crate::repository::read_repositories;
+use crate::timeline::{build_timelines, build_trend};
+use axum::extract::{Path, Query};
+use axum::http::HeaderMap;
+use axum::response::{Html, Json, Response};
+use axum::response::sse::{Event, KeepAlive, Sse};
 use axum::routing::{get, post};
 use axum::{Extension, Router};
 use cached::proc_macro::cached;
+use common::config::{Configurable, WebConfig};
 use common::environment::{VERSION, load_dotenv};
 use common::errors::get_error_descriptions;
-use database::entities::package_metadata;
-use database::{Database, connect_to_db};
-use log::{error, info};
+use common::routing::log_stream_name;
+use common::types::{LogChunkTransmissionFormat, RunningBuild};
+use common::{RETRY_TIMEOUT, amqp_address, get_rand_string};
+use futures_util::future::ready;
+use futures_util::{Stream, StreamExt};
+use lapin::{Connection, ConnectionProperties};
+use lapin::options::{BasicAckOptions, BasicConsumeOptions, BasicQosOptions, QueueDeclareOptions};
+use lapin::types::{AMQPValue, FieldTable};
+use database::entities::{package_metadata, workers};
+use database::{Database, FileOwner, connect_to_db};
+use log::{error, info, warn};
 use reqwest::StatusCode;
+use serde::Deserialize;
 use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::env;
 use std::process::exit;
+use std::time::Duration;
 use tera::{Context, Tera, Value, to_value};
+use tokio::sync::RwLock;
+use tokio::time::sleep;
+
+/// How many files a search returns at most
+const FILE_SEARCH_LIMIT: u64 = 500;
+
+#[derive(Deserialize)]
+struct FileSearch {
+    /// File name or path to look for
+    q: Option<String>,
+}
+
+/// Connection to RabbitMQ for the live logs, missing while the server is unreachable
+type LiveLogConnection = Arc<RwLock<Option<Connection>>>;
 
 fn error_desc_filter(error: &Value, _: &HashMap<String, Value>) -> Result<Value, tera::Error> {
     let code = error.as_i64().unwrap_or(-1);
@@ -25,7 +62,10 @@ async fn main() {
 
     info!("Starting Aur-Builder Web v{VERSION}");
 
+    let config = WebConfig::new(env::var("AB_CONFIG_PATH").ok()).unwrap();
     let db = connect_to_db().await;
+    let amqp: LiveLogConnection = Arc::new(RwLock::new(None));
+    connect_live_logs(amqp.clone());
     let mut tera = match Tera::new("web/src/templates/**/*.html") {
         Ok(t) => t,
         Err(e) => {
@@ -42,18 +82,78 @@ async fn main() {
         .route("/build-results/{pid}", get(render_build_results_function))
         .route("/build-log/{pid}", get(render_build_log_function))
         .route("/force-rebuild/{pid}", post(init_force_rebuild))
+        .route("/workers", get(render_workers_function))
+        .route("/repositories", get(render_repositories_function))
+        .route("/files", get(render_file_search_function))
+        .route("/api/files", get(search_files))
+        .route("/repo/{name}/{arch}/", get(render_repository_index))
+        .route("/repo/{name}/{arch}/{file}", get(serve_repository_file))
+        .route("/live-log/{build_id}", get(render_live_log_function))
+        .route("/live-log/{build_id}/events", get(stream_live_log))
         .nest_service(
             "/assets",
             tower_http::services::ServeDir::new("web/src/assets"),
         )
         .layer(Extension(tera))
-        .layer(Extension(db));
+        .layer(Extension(db))
+        .layer(Extension(amqp))
+        .layer(Extension(config));
 
     // run our app with hyper, listening globally on port 3000
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
     axum::serve(listener, app).await.unwrap();
 }
 
+/// Keeps the connection of the live logs open in the background.
+///
+/// RabbitMQ is only needed for the live logs, so the rest of the web UI works while it is unreachable.
+fn connect_live_logs(amqp: LiveLogConnection) {
+    tokio::spawn(async move {
+        let mut available = true;
+        loop {
+            let connected = amqp.read().await.as_ref().is_some_and(|c| c.status().connected());
+            if !connected {
+                match Connection::connect(&amqp_address(), ConnectionProperties::default()).await {
+                    Ok(connection) => {
+                        info!("Connected to rabbitmq, live logs are available");
+                        *amqp.write().await = Some(connection);
+                        available = true;
+                    }
+                    Err(e) if available => {
+                        warn!("Failed to connect to AMQP server, live logs are unavailable: {}", e);
+                        available = false;
+                    }
+                    Err(_) => {}
+                }
+            }
+            sleep(Duration::from_secs(RETRY_TIMEOUT as u64)).await;
+        }
+    });
+}
+
+/// Returns the builds a worker reported as running in its last heartbeat
+fn running_builds(worker: &workers::Model) -> Vec<RunningBuild> {
+    worker
+        .current_builds
+        .as_ref()
+        .and_then(|b| serde_json::from_str(b).ok())
+        .unwrap_or_default()
+}
+
+/// Groups the rows of the given builds by build id, every build gets an entry even if it has no rows
+fn group_by_build<T>(build_ids: &[i32], rows: Vec<T>, build_id: impl Fn(&T) -> i64) -> HashMap<String, Vec<T>> {
+    let mut grouped: HashMap<String, Vec<T>> = build_ids
+        .iter()
+        .map(|id| (id.to_string(), Vec::new()))
+        .collect();
+    for row in rows {
+        if let Some(list) = grouped.get_mut(&build_id(&row).to_string()) {
+            list.push(row);
+        }
+    }
+    grouped
+}
+
 async fn render_packages_function(
     Extension(tera): Extension<Tera>,
     Extension(db): Extension<Database>,
@@ -68,6 +168,204 @@ async fn render_packages_function(
     Html(tera.render("index.html", &context).unwrap())
 }
 
+async fn render_workers_function(
+    Extension(tera): Extension<Tera>,
+    Extension(db): Extension<Database>,
+) -> Html<String> {
+    let mut context = Context::new();
+    let workers = db.get_workers().await.unwrap();
+    let running_builds: HashMap<String, Vec<RunningBuild>> = workers
+        .iter()
+        .map(|w| (w.id.clone(), running_builds(w)))
+        .collect();
+
+    context.insert("version", VERSION);
+
+    context.insert("workers", &workers);
+    context.insert("running_builds", &running_builds);
+
+    Html(tera.render("workers.html", &context).unwrap())
+}
+
+async fn render_repositories_function(
+    Extension(tera): Extension<Tera>,
+    Extension(config): Extension<WebConfig>,
+) -> Html<String> {
+    let mut context = Context::new();
+    let repositories = match config.repositories {
+        Some(root) => tokio::task::spawn_blocking(move || read_repositories(std::path::Path::new(&root)))
+            .await
+            .unwrap(),
+        None => Vec::new(),
+    };
+
+    context.insert("version", VERSION);
+
+    context.insert("repositories", &repositories);
+
+    Html(tera.render("repositories.html", &context).unwrap())
+}
+
+async fn render_file_search_function(
+    Extension(tera): Extension<Tera>,
+    Extension(db): Extension<Database>,
+    Query(search): Query<FileSearch>,
+) -> Html<String> {
+    let mut context = Context::new();
+    let term = search.q.unwrap_or_default();
+    let owners = match term.trim().is_empty() {
+        true => Vec::new(),
+        false => db.find_file_owners(&term, FILE_SEARCH_LIMIT).await.unwrap(),
+    };
+
+    context.insert("version", VERSION);
+
+    context.insert("term", &term);
+    context.insert("owners", &owners);
+    context.insert("limit", &FILE_SEARCH_LIMIT);
+
+    Html(tera.render("file-search.html", &context).unwrap())
+}
+
+/// Returns the packages installing a file as JSON, e.g. `/api/files?q=usr/bin/foo`
+async fn search_files(
+    Extension(db): Extension<Database>,
+    Query(search): Query<FileSearch>,
+) -> Result<Json<Vec<FileOwner>>, StatusCode> {
+    let term = search.q.filter(|q| !q.trim().is_empty()).ok_or(StatusCode::BAD_REQUEST)?;
+    db.find_file_owners(&term, FILE_SEARCH_LIMIT)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("Failed to search for {term}: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+async fn render_repository_index(
+    Extension(tera): Extension<Tera>,
+    Extension(config): Extension<WebConfig>,
+    Path((name, arch)): Path<(String, String)>,
+) -> Result<Html<String>, StatusCode> {
+    let dir = config
+        .repositories
+        .and_then(|root| repository_dir(&root, &name, &arch))
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let files = list_files(&dir).await.map_err(|_| StatusCode::NOT_FOUND)?;
+
+    let mut context = Context::new();
+    context.insert("version", VERSION);
+    context.insert("name", &name);
+    context.insert("arch", &arch);
+    context.insert("files", &files);
+
+    Ok(Html(tera.render("repo-index.html", &context).unwrap()))
+}
+
+/// Serves the databases, packages and signatures of a repository to pacman
+async fn serve_repository_file(
+    Extension(config): Extension<WebConfig>,
+    Path((name, arch, file)): Path<(String, String, String)>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let dir = config
+        .repositories
+        .and_then(|root| repository_dir(&root, &name, &arch))
+        .ok_or(StatusCode::NOT_FOUND)?;
+    if !is_servable(&file) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    serve_file(&dir.join(file), &name, &headers).await
+}
+
+async fn render_live_log_function(
+    Extension(tera): Extension<Tera>,
+    Path(build_id): Path<String>,
+) -> Html<String> {
+    let mut context = Context::new();
+
+    context.insert("version", VERSION);
+
+    context.insert("build_id", &build_id);
+
+    Html(tera.render("live-log.html", &context).unwrap())
+}
+
+/// Relays the log stream of a running build as server-sent events.
+///
+/// The stream is read from its start, so lines produced before the page was opened are sent first.
+async fn stream_live_log(
+    Extension(amqp): Extension<LiveLogConnection>,
+    Path(build_id): Path<String>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, StatusCode> {
+    let channel = match amqp.read().await.as_ref() {
+        Some(connection) => connection
+            .create_channel()
+            .await
+            .map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?,
+        None => return Err(StatusCode::SERVICE_UNAVAILABLE),
+    };
+    let stream_name = log_stream_name(&build_id);
+    channel
+        .queue_declare(
+            &stream_name,
+            QueueDeclareOptions {
+                passive: true,
+                ..QueueDeclareOptions::default()
+            },
+            FieldTable::default(),
+        )
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+    // Stream queues require a prefetch limit
+    channel
+        .basic_qos(100, BasicQosOptions::default())
+        .await
+        .map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let mut arguments = FieldTable::default();
+    arguments.insert("x-stream-offset".into(), AMQPValue::LongString("first".into()));
+    let consumer = channel
+        .basic_consume(
+            &stream_name,
+            &format!("aur-builder-web-{}", get_rand_string()),
+            BasicConsumeOptions::default(),
+            arguments,
+        )
+        .await
+        .map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let events = consumer
+        .then(move |delivery| {
+            // Keep the channel open as long as the stream is consumed
+            let _channel = channel.clone();
+            async move {
+                let delivery = delivery.ok()?;
+                delivery.ack(BasicAckOptions::default()).await.ok()?;
+                serde_json::from_slice::<LogChunkTransmissionFormat>(&delivery.data).ok()
+            }
+        })
+        .take_while(|chunk| ready(chunk.is_some()))
+        .filter_map(ready)
+        .scan(false, |finished, chunk| {
+            if *finished {
+                return ready(None);
+            }
+            *finished = chunk.finished;
+            ready(Some(chunk))
+        })
+        .map(|chunk| {
+            let event = Event::default()
+                .id(chunk.sequence.to_string())
+                .event(if chunk.finished { "end" } else { "log" })
+                .json_data(&chunk.lines)
+                .unwrap();
+            Ok(event)
+        });
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
 async fn render_build_results_function(
     Extension(tera): Extension<Tera>,
     Extension(db): Extension<Database>,
@@ -85,7 +383,38 @@ async fn render_build_results_function(
 
     context.insert("package", &package);
 
+    let running: Vec<RunningBuild> = db
+        .get_workers()
+        .await
+        .unwrap()
+        .iter()
+        .filter(|w| w.online)
+        .flat_map(running_builds)
+        .filter(|b| b.package_id == package.id)
+        .collect();
+    context.insert("running_builds", &running);
+
     let build_results = db.get_build_results(package.id).await.unwrap();
+    let build_ids: Vec<i32> = build_results.iter().map(|b| b.id).collect();
+    let phases = db.get_build_phases(build_ids.clone()).await.unwrap();
+    let uploads = db.get_artifact_uploads(build_ids.clone()).await.unwrap();
+    context.insert("uploads", &group_by_build(&build_ids, uploads, |r| r.build_result_id));
+    let dependencies = db.get_build_dependencies(build_ids.clone()).await.unwrap();
+    context.insert("dependencies", &group_by_build(&build_ids, dependencies, |r| r.build_result_id));
+    let cache_stats = db.get_build_cache_stats(build_ids.clone()).await.unwrap();
+    context.insert("cache_stats", &group_by_build(&build_ids, cache_stats, |r| r.build_result_id));
+    let smoke_tests = db.get_smoke_tests(build_ids.clone()).await.unwrap();
+    context.insert("smoke_tests", &group_by_build(&build_ids, smoke_tests, |r| r.build_result_id));
+    let lint_findings = db.get_lint_findings(build_ids.clone()).await.unwrap();
+    context.insert("lint_findings", &group_by_build(&build_ids, lint_findings, |r| r.build_result_id));
+    let differences = db.get_reproducibility_differences(build_ids.clone()).await.unwrap();
+    context.insert("differences", &group_by_build(&build_ids, differences, |r| r.build_result_id));
+    let built_packages = db.get_built_packages(build_ids.clone()).await.unwrap();
+    context.insert("built_packages", &group_by_build(&build_ids, built_packages, |r| r.build_result_id));
+    let build_environments = db.get_build_environments(build_ids.clone()).await.unwrap();
+    context.insert("build_environments", &group_by_build(&build_ids, build_environments, |r| r.build_result_id));
+    context.insert("timelines", &build_timelines(&build_results, &phases));
+    context.insert("trend", &build_trend(&build_results, &phases));
     context.insert("build_results", &build_results);
 
     Ok(Html(tera.render("build-results.html", &context).unwrap()))
@@ -0,0 +1,163 @@
+use axum::body::Body;
+use axum::http::header::{
+    AsHeaderName,
+    ACCEPT_RANGES, CACHE_CONTROL, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE,
+    IF_NONE_MATCH, IF_RANGE, LAST_MODIFIED, RANGE,
+};
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use common::http::{ByteRange, parse_range};
+use repo_db::is_database_file;
+use serde::Serialize;
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio_util::io::ReaderStream;
+
+#[derive(Serialize, Debug)]
+pub struct RepositoryFile {
+    pub name: String,
+    pub size: u64,
+    pub modified: u64,
+}
+
+/// Returns whether a path segment may be served, hidden files like locks and partial uploads are not
+pub fn is_servable(segment: &str) -> bool {
+    !segment.is_empty() && !segment.starts_with('.') && !segment.contains(['/', '\\'])
+}
+
+/// Returns the directory of the repository `name` for `arch` below `root`
+pub fn repository_dir(root: &str, name: &str, arch: &str) -> Option<PathBuf> {
+    if !is_servable(name) || !is_servable(arch) {
+        return None;
+    }
+    Some(Path::new(root).join(name).join(arch))
+}
+
+/// Lists the files of a repository directory, sorted by name
+pub async fn list_files(dir: &Path) -> std::io::Result<Vec<RepositoryFile>> {
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    let mut files = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !is_servable(&name) {
+            continue;
+        }
+        // follows the `<repo>.db` links
+        let Ok(metadata) = tokio::fs::metadata(entry.path()).await else {
+            continue;
+        };
+        if metadata.is_file() {
+            files.push(RepositoryFile {
+                name,
+                size: metadata.len(),
+                modified: unix_seconds(metadata.modified().unwrap_or(UNIX_EPOCH)),
+            });
+        }
+    }
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(files)
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+/// Returns whether a comma separated list of entity tags contains `etag`
+fn etag_matches(header: &str, etag: &str) -> bool {
+    header
+        .split(',')
+        .map(|t| t.trim().trim_start_matches("W/"))
+        .any(|t| t == "*" || t == etag)
+}
+
+fn header_str(headers: &HeaderMap, name: impl AsHeaderName) -> Option<&str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+/// Serves a file of the directory of repository `repo`.
+///
+/// Supports single byte ranges as well as conditional requests with `ETag` and `Last-Modified`.
+pub async fn serve_file(path: &Path, repo: &str, headers: &HeaderMap) -> Result<Response, StatusCode> {
+    let metadata = tokio::fs::metadata(path)
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+    if !metadata.is_file() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let size = metadata.len();
+    let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+    let etag = format!(
+        "\"{size:x}-{:x}\"",
+        modified.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos()
+    );
+    let last_modified = httpdate::fmt_http_date(modified);
+
+    let filename = path
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    response_headers.insert(ETAG, HeaderValue::from_str(&etag).unwrap());
+    response_headers.insert(LAST_MODIFIED, HeaderValue::from_str(&last_modified).unwrap());
+    response_headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static("application/octet-stream"),
+    );
+    // databases change with every published package, package files never do
+    let is_database = is_database_file(&filename, repo);
+    response_headers.insert(
+        CACHE_CONTROL,
+        HeaderValue::from_static(if is_database { "no-cache" } else { "public, max-age=86400" }),
+    );
+
+    let not_modified = match header_str(headers, IF_NONE_MATCH) {
+        Some(tags) => etag_matches(tags, &etag),
+        None => header_str(headers, IF_MODIFIED_SINCE)
+            .and_then(|since| httpdate::parse_http_date(since).ok())
+            .is_some_and(|since| unix_seconds(modified) <= unix_seconds(since)),
+    };
+    if not_modified {
+        return Ok((StatusCode::NOT_MODIFIED, response_headers).into_response());
+    }
+
+    // a range only applies if the file still is the one the client has partially
+    let range_applies = match header_str(headers, IF_RANGE) {
+        Some(validator) => validator == etag || validator == last_modified,
+        None => true,
+    };
+    let range = header_str(headers, RANGE)
+        .filter(|_| range_applies)
+        .and_then(|r| parse_range(r, size));
+
+    let (status, start, length) = match range {
+        None => (StatusCode::OK, 0, size),
+        Some(ByteRange::Unsatisfiable) => {
+            response_headers.insert(
+                CONTENT_RANGE,
+                HeaderValue::from_str(&format!("bytes */{size}")).unwrap(),
+            );
+            return Ok((StatusCode::RANGE_NOT_SATISFIABLE, response_headers).into_response());
+        }
+        Some(ByteRange::Satisfiable(start, end)) => {
+            response_headers.insert(
+                CONTENT_RANGE,
+                HeaderValue::from_str(&format!("bytes {start}-{end}/{size}")).unwrap(),
+            );
+            (StatusCode::PARTIAL_CONTENT, start, end - start + 1)
+        }
+    };
+    response_headers.insert(CONTENT_LENGTH, HeaderValue::from(length));
+
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+    file.seek(SeekFrom::Start(start))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let body = Body::from_stream(ReaderStream::new(file.take(length)));
+
+    Ok((status, response_headers, body).into_response())
+}
@@ -0,0 +1,76 @@
+use repo_db::Repository;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+#[derive(Serialize, Debug)]
+pub struct RepositoryPackage {
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    pub filename: String,
+    /// Size of the package file in bytes
+    pub size: u64,
+    pub build_date: Option<i64>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct RepositoryView {
+    pub name: String,
+    pub arch: String,
+    pub packages: Vec<RepositoryPackage>,
+    pub error: Option<String>,
+}
+
+fn subdirectories(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.path().is_dir())
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+fn read_repository(root: &Path, name: &str, arch: &str) -> RepositoryView {
+    let repository = Repository::new(root.join(name).join(arch), name);
+    let (packages, error) = match repository.entries() {
+        Ok(entries) => (
+            entries
+                .into_values()
+                .map(|e| RepositoryPackage {
+                    name: e.desc.name().to_string(),
+                    version: e.desc.version().to_string(),
+                    description: e.desc.first("DESC").unwrap_or_default().to_string(),
+                    filename: e.desc.filename().to_string(),
+                    size: e.desc.first("CSIZE").and_then(|s| s.parse().ok()).unwrap_or(0),
+                    build_date: e.desc.first("BUILDDATE").and_then(|d| d.parse().ok()),
+                })
+                .collect(),
+            None,
+        ),
+        Err(e) => (Vec::new(), Some(e.to_string())),
+    };
+    RepositoryView {
+        name: name.to_string(),
+        arch: arch.to_string(),
+        packages,
+        error,
+    }
+}
+
+/// Reads all repositories below `root`, which is laid out as `<root>/<name>/<arch>/`
+pub fn read_repositories(root: &Path) -> Vec<RepositoryView> {
+    subdirectories(root)
+        .iter()
+        .flat_map(|name| {
+            subdirectories(&root.join(name))
+                .into_iter()
+                .map(move |arch| read_repository(root, name, &arch))
+        })
+        .collect()
+}
@@ -0,0 +1,102 @@
+use database::entities::{build_phases, build_results};
+use serde::Serialize;
+
+/// Number of builds shown in the phase duration trend of a package
+pub const TREND_LENGTH: usize = 20;
+
+#[derive(Serialize, Debug)]
+pub struct PhaseSegment {
+    pub phase: String,
+    pub seconds: f64,
+    /// Share of the whole build
+    pub percent: f64,
+    pub failed: bool,
+    pub failing_item: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct BuildTimeline {
+    pub build_result_id: i32,
+    pub version: Option<String>,
+    pub started_at: Option<String>,
+    pub total_seconds: f64,
+    /// Length compared to the longest build of the trend
+    pub percent: f64,
+    pub segments: Vec<PhaseSegment>,
+}
+
+fn build_timeline(
+    build_result: &build_results::Model,
+    phases: &[build_phases::Model],
+) -> BuildTimeline {
+    let durations: Vec<(&build_phases::Model, f64)> = phases
+        .iter()
+        .filter(|p| p.build_result_id == build_result.id as i64)
+        .map(|p| {
+            let millis = (p.finished_at - p.started_at).num_milliseconds().max(0);
+            (p, millis as f64 / 1000.0)
+        })
+        .collect();
+    let total_seconds: f64 = durations.iter().map(|(_, d)| d).sum();
+
+    let segments = durations
+        .into_iter()
+        .map(|(p, seconds)| PhaseSegment {
+            phase: p.phase.clone(),
+            seconds,
+            percent: if total_seconds > 0.0 {
+                seconds / total_seconds * 100.0
+            } else {
+                0.0
+            },
+            failed: p.exit_status != 0,
+            failing_item: p.failing_item.clone(),
+        })
+        .collect();
+
+    BuildTimeline {
+        build_result_id: build_result.id,
+        version: build_result.version.clone(),
+        started_at: build_result
+            .started_at
+            .map(|s| s.format("%Y-%m-%d %H:%M").to_string()),
+        total_seconds,
+        percent: 100.0,
+        segments,
+    }
+}
+
+/// Creates one timeline per build result, in the same order
+pub fn build_timelines(
+    build_results: &[build_results::Model],
+    phases: &[build_phases::Model],
+) -> Vec<BuildTimeline> {
+    build_results
+        .iter()
+        .map(|b| build_timeline(b, phases))
+        .collect()
+}
+
+/// Creates the trend of the newest builds that have phase timings, oldest first
+pub fn build_trend(
+    build_results: &[build_results::Model],
+    phases: &[build_phases::Model],
+) -> Vec<BuildTimeline> {
+    let mut trend: Vec<BuildTimeline> = build_results
+        .iter()
+        .map(|b| build_timeline(b, phases))
+        .filter(|t| !t.segments.is_empty())
+        .take(TREND_LENGTH)
+        .collect();
+    trend.reverse();
+
+    let longest = trend.iter().map(|t| t.total_seconds).fold(0.0, f64::max);
+    for timeline in &mut trend {
+        timeline.percent = if longest > 0.0 {
+            timeline.total_seconds / longest * 100.0
+        } else {
+            0.0
+        };
+    }
+    trend
+}
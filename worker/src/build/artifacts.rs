@@ -0,0 +1,139 @@
+use crate::publish::{Publisher, file_sha256};
+use crate::sign::GpgSigner;
+use common::errors::{
+    CHECKSUM_MISMATCH_CODE, EXTRACTION_FAILED_CODE, SIGNING_FAILED_CODE, UPLOAD_FAILED_CODE,
+};
+use common::types::{BuildResultTransmissionFormat, Phase, PhaseReport};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
+pub fn fail(results: &mut BuildResultTransmissionFormat, code: i64, message: String) {
+    error!("Build {}: {}", results.build_id, message);
+    results.log_lines.push(format!("stderr: worker: {message}\n"));
+    results.status_code = code;
+    results.success = false;
+}
+
+/// Creates the detached signature `<file>.sig` of a package and returns its path and checksum
+async fn sign_file(signer: Arc<GpgSigner>, file: PathBuf) -> Result<(PathBuf, String), String> {
+    let mut signature = file.clone().into_os_string();
+    signature.push(".sig");
+    let signature = PathBuf::from(signature);
+    let target = signature.clone();
+    tokio::task::spawn_blocking(move || signer.sign(&file, &target))
+        .await
+        .map_err(|e| e.to_string())??;
+    let checksum = file_sha256(signature.clone())
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok((signature, checksum))
+}
+
+/// Verifies the checksums of the package files extracted from the build container, signs and publishes them.
+///
+/// Every file is published to every destination, the outcome of each is added to `results.uploads`.
+/// Signatures are published before their package, so repository databases can include them.
+/// The publishing is added to the build report as the `upload` phase.
+pub async fn publish_artifacts(
+    results: &mut BuildResultTransmissionFormat,
+    results_dir: &Path,
+    publishers: &[Box<dyn Publisher>],
+    signer: Option<Arc<GpgSigner>>,
+) {
+    let Some(report) = results.report.clone() else {
+        fail(results, EXTRACTION_FAILED_CODE, "no build report, can't find the built packages".to_string());
+        return;
+    };
+
+    for file in &report.produced_files {
+        match file_sha256(results_dir.join(&file.name)).await {
+            Ok(checksum) if checksum == file.sha256 => {}
+            Ok(checksum) => {
+                let message = format!(
+                    "checksum of {} is {checksum}, expected {}",
+                    file.name, file.sha256
+                );
+                fail(results, CHECKSUM_MISMATCH_CODE, message);
+                return;
+            }
+            Err(e) => {
+                fail(results, EXTRACTION_FAILED_CODE, format!("can't read {}: {e}", file.name));
+                return;
+            }
+        }
+    }
+
+    let mut signatures = Vec::new();
+    if let Some(signer) = signer {
+        for file in &report.produced_files {
+            match sign_file(signer.clone(), results_dir.join(&file.name)).await {
+                Ok(signature) => signatures.push(Some(signature)),
+                Err(e) => {
+                    fail(results, SIGNING_FAILED_CODE, format!("can't sign {}: {e}", file.name));
+                    return;
+                }
+            }
+        }
+        results.log_lines.push(format!(
+            "stdout: worker: signed {} package(s) with key {}\n",
+            signatures.len(),
+            signer.fingerprint()
+        ));
+        results.signing_key = Some(signer.fingerprint().to_string());
+    } else {
+        signatures.resize(report.produced_files.len(), None);
+    }
+
+    let started_at = now_millis();
+    let mut failing_item = None;
+    for (file, signature) in report.produced_files.iter().zip(&signatures) {
+        for publisher in publishers {
+            let mut artifacts = Vec::new();
+            if let Some((path, checksum)) = signature
+                && publisher.accepts_signatures()
+            {
+                artifacts.push((path.clone(), checksum.clone()));
+            }
+            artifacts.push((results_dir.join(&file.name), file.sha256.clone()));
+
+            for (path, checksum) in artifacts {
+                let upload = publisher.publish(&path, &checksum).await;
+                let success = upload.success;
+                results.log_lines.push(format!(
+                    "stdout: worker: publishing {} to {} finished after {} attempt(s) with status {:?}\n",
+                    upload.file, upload.destination, upload.attempts, upload.status
+                ));
+                if !success && failing_item.is_none() {
+                    failing_item = Some(format!("{} ({})", upload.file, publisher.name()));
+                }
+                results.uploads.push(upload);
+            }
+        }
+    }
+
+    let exit_status = if failing_item.is_some() {
+        UPLOAD_FAILED_CODE as i32
+    } else {
+        0
+    };
+    if let Some(report) = results.report.as_mut() {
+        report.phases.push(PhaseReport {
+            phase: Phase::Upload,
+            started_at,
+            finished_at: now_millis(),
+            exit_status,
+            failing_item: failing_item.clone(),
+        });
+    }
+    if let Some(file) = failing_item {
+        fail(results, UPLOAD_FAILED_CODE, format!("failed to publish {file}"));
+    }
+}
@@ -0,0 +1,182 @@
+use common::config::CacheSettings;
+use std::fs::{self, File, OpenOptions};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::SystemTime;
+
+/// Mount point of the source and compiler caches inside the build container
+const CACHE_MOUNT: &str = "/cache";
+
+/// Package cache of pacman inside the build container
+const PACMAN_CACHE: &str = "/var/cache/pacman/pkg";
+
+const SOURCES_DIR: &str = "sources";
+const PACKAGES_DIR: &str = "packages";
+const CCACHE_DIR: &str = "ccache";
+const SCCACHE_DIR: &str = "sccache";
+
+/// Held shared by every build using the caches and exclusively by the eviction
+const LOCK_FILE: &str = ".lock";
+/// Taken exclusively by a waiting eviction, so no further builds start using the caches until it ran
+const GATE_FILE: &str = ".lock-gate";
+
+static EVICTION_PENDING: AtomicBool = AtomicBool::new(false);
+
+/// Creates the cache directories, writable for every user since pacman runs as root and makepkg doesn't
+fn prepare_cache_dirs(settings: &CacheSettings) -> std::io::Result<()> {
+    for dir in [SOURCES_DIR, PACKAGES_DIR, CCACHE_DIR, SCCACHE_DIR] {
+        let path = Path::new(&settings.path).join(dir);
+        fs::create_dir_all(&path)?;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o777))?;
+    }
+    Ok(())
+}
+
+/// Adds the bind mounts and environment variables of the caches to a build container
+pub fn add_cache_mounts(settings: &CacheSettings, env: &mut Vec<String>, binds: &mut Vec<String>) {
+    if let Err(e) = prepare_cache_dirs(settings) {
+        warn!("Failed to create cache directories in {}, building without caches: {}", settings.path, e);
+        return;
+    }
+    let host_dir = |dir: &str| Path::new(&settings.path).join(dir).to_string_lossy().to_string();
+
+    binds.push(format!("{}:{CACHE_MOUNT}/{SOURCES_DIR}", host_dir(SOURCES_DIR)));
+    env.push(format!("SRCDEST={CACHE_MOUNT}/{SOURCES_DIR}"));
+
+    binds.push(format!("{}:{PACMAN_CACHE}", host_dir(PACKAGES_DIR)));
+    env.push(format!("AB_PACKAGE_CACHE={PACMAN_CACHE}"));
+
+    if settings.ccache.unwrap_or(false) {
+        binds.push(format!("{}:{CACHE_MOUNT}/{CCACHE_DIR}", host_dir(CCACHE_DIR)));
+        env.push(format!("AB_CCACHE_DIR={CACHE_MOUNT}/{CCACHE_DIR}"));
+        if let Some(max_size) = settings.ccache_max_size {
+            env.push(format!("CCACHE_MAXSIZE={max_size}Mi"));
+        }
+    }
+    if settings.sccache.unwrap_or(false) {
+        binds.push(format!("{}:{CACHE_MOUNT}/{SCCACHE_DIR}", host_dir(SCCACHE_DIR)));
+        env.push(format!("AB_SCCACHE_DIR={CACHE_MOUNT}/{SCCACHE_DIR}"));
+        if let Some(max_size) = settings.sccache_max_size {
+            env.push(format!("SCCACHE_CACHE_SIZE={max_size}M"));
+        }
+    }
+}
+
+/// Returns the size of a file or of everything below a directory
+fn entry_size(path: &Path) -> u64 {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|e| entry_size(&e.path())).sum())
+        .unwrap_or(0)
+}
+
+/// Opens one of the lock files of the caches
+fn open_lock(settings: &CacheSettings, name: &str) -> std::io::Result<File> {
+    fs::create_dir_all(&settings.path)?;
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(Path::new(&settings.path).join(name))
+}
+
+/// Takes the lock every build using the caches holds, it is released when the file is dropped.
+///
+/// The lock is shared with the builds of other workers using the same cache directory.
+pub async fn lock_shared(settings: &CacheSettings) -> Option<File> {
+    let settings = settings.clone();
+    let locked = tokio::task::spawn_blocking(move || {
+        // passing the gate waits for a pending eviction, so it can't be starved by overlapping builds
+        let gate = open_lock(&settings, GATE_FILE)?;
+        gate.lock_shared()?;
+        let lock = open_lock(&settings, LOCK_FILE)?;
+        lock.lock_shared()?;
+        Ok::<File, std::io::Error>(lock)
+    })
+    .await
+    .map_err(std::io::Error::other)
+    .and_then(|locked| locked);
+    match locked {
+        Ok(lock) => Some(lock),
+        Err(e) => {
+            warn!("Failed to lock the build caches: {}", e);
+            None
+        }
+    }
+}
+
+/// Returns the top-level entries of the sources and packages with their size and time of last use
+fn cache_entries(settings: &CacheSettings) -> Vec<(PathBuf, u64, SystemTime)> {
+    [SOURCES_DIR, PACKAGES_DIR]
+        .iter()
+        .filter_map(|dir| fs::read_dir(Path::new(&settings.path).join(dir)).ok())
+        .flat_map(|entries| entries.flatten())
+        .filter_map(|entry| {
+            let used = entry.metadata().and_then(|m| m.modified()).ok()?;
+            Some((entry.path(), entry_size(&entry.path()), used))
+        })
+        .collect()
+}
+
+/// Removes the least recently used sources and packages until they are smaller than `max_size` MiB.
+///
+/// An entry is a downloaded file or a VCS checkout, which is only removed as a whole. The builder agent
+/// touches the entries a build used, so their modification time is the time of their last use.
+/// The size is measured without locks first, only an eviction waits until no build uses the caches.
+/// The compiler caches are limited by ccache and sccache themselves. Returns the number of bytes freed.
+pub fn evict_caches(settings: &CacheSettings, max_size: u64) -> std::io::Result<u64> {
+    let limit = max_size * 1024 * 1024;
+    let total: u64 = cache_entries(settings).iter().map(|(_, size, _)| size).sum();
+    if total <= limit {
+        return Ok(0);
+    }
+
+    let gate = open_lock(settings, GATE_FILE)?;
+    gate.lock()?;
+    let lock = open_lock(settings, LOCK_FILE)?;
+    lock.lock()?;
+
+    // builds may have changed the caches while waiting for the lock
+    let mut entries = cache_entries(settings);
+    let mut total: u64 = entries.iter().map(|(_, size, _)| size).sum();
+    entries.sort_by_key(|(_, _, used)| *used);
+
+    let mut freed = 0;
+    for (path, size, _) in entries {
+        if total <= limit {
+            break;
+        }
+        let removed = match path.is_dir() {
+            true => fs::remove_dir_all(&path),
+            false => fs::remove_file(&path),
+        };
+        match removed {
+            Ok(()) => freed += size,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        total -= size;
+    }
+    Ok(freed)
+}
+
+/// Checks the size of the caches in the background and evicts them if needed, unless an eviction is already waiting.
+pub fn schedule_eviction(settings: CacheSettings, max_size: u64) {
+    if EVICTION_PENDING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    tokio::task::spawn_blocking(move || {
+        let evicted = evict_caches(&settings, max_size);
+        EVICTION_PENDING.store(false, Ordering::SeqCst);
+        match evicted {
+            Ok(0) => {}
+            Ok(freed) => info!("Evicted {} MiB from the build caches", freed / 1024 / 1024),
+            Err(e) => warn!("Failed to evict build caches: {}", e),
+        }
+    });
+}
@@ -0,0 +1,121 @@
+use bollard::Docker;
+use bollard::container::{Config, CreateContainerOptions, StartContainerOptions, WaitContainerOptions};
+use bollard::models::HostConfig;
+use crate::build::docker::{collect_logs, get_image_name};
+use common::config::ChrootSettings;
+use futures_util::StreamExt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::time::sleep;
+
+/// Mount point of the chroot cache directory inside the containers
+pub const CHROOT_CACHE_MOUNT: &str = "/chroot-cache";
+
+/// File name of the base chroot archive in the cache directory
+pub const BASE_CHROOT_ARCHIVE: &str = "base-chroot.tar.gz";
+
+const DEFAULT_REFRESH_INTERVAL: u64 = 24;
+
+/// Seconds between two checks of the age of the base chroot
+const CHECK_INTERVAL: u64 = 60 * 60;
+
+/// Set once the base chroot has been checked or created successfully
+static BASE_CHROOT_AVAILABLE: AtomicBool = AtomicBool::new(false);
+
+pub fn base_chroot_available() -> bool {
+    BASE_CHROOT_AVAILABLE.load(Ordering::Relaxed)
+}
+
+/// Returns the script that recreates the base chroot archive if it's older than `max_age` minutes.
+///
+/// The archive is written next to the old one and renamed, so running builds keep reading a complete file.
+fn refresh_script(max_age: u64) -> String {
+    format!(
+        r#"set -e
+archive={CHROOT_CACHE_MOUNT}/{BASE_CHROOT_ARCHIVE}
+if [ -n "$(find "$archive" -mmin -{max_age} 2>/dev/null)" ]; then
+    echo "base chroot is up to date"
+    exit 0
+fi
+rm -rf /tmp/chroot
+mkdir -p /tmp/chroot
+mkarchroot /tmp/chroot/root base-devel
+tar --numeric-owner -C /tmp/chroot/root -czf "$archive.part" .
+mv "$archive.part" "$archive"
+"#
+    )
+}
+
+/// Capabilities systemd-nspawn needs to set up the namespaces and mounts of a chroot
+const NSPAWN_CAPABILITIES: [&str; 2] = ["SYS_ADMIN", "MKNOD"];
+
+/// Lets a build container run systemd-nspawn without making it privileged.
+///
+/// The container gets no host devices and runs as root, but the agent removes the sudo rights of the builder user
+/// first. The package code only runs as that user, without a way to regain privileges, or inside the chroot.
+pub fn allow_nspawn(host_config: &mut HostConfig) {
+    host_config.cap_add = Some(NSPAWN_CAPABILITIES.iter().map(|c| c.to_string()).collect());
+    // the default profiles block the mount and namespace syscalls
+    host_config.security_opt = Some(vec![
+        "seccomp=unconfined".to_string(),
+        "apparmor=unconfined".to_string(),
+    ]);
+}
+
+/// Runs a privileged builder container that creates the base chroot if it's missing or outdated.
+///
+/// It only installs packages from the official repositories, package code never runs with these privileges.
+async fn refresh_base_chroot(settings: &ChrootSettings) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let docker = Docker::connect_with_local_defaults()?;
+    let max_age = settings.refresh_interval.unwrap_or(DEFAULT_REFRESH_INTERVAL) * 60;
+
+    let create_container_config = Config {
+        image: Some(get_image_name()),
+        user: Some("root".to_string()),
+        cmd: Some(vec!["bash".to_string(), "-c".to_string(), refresh_script(max_age)]),
+        host_config: Some(HostConfig {
+            privileged: Some(true),
+            binds: Some(vec![format!("{}:{CHROOT_CACHE_MOUNT}", settings.path)]),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let container = docker
+        .create_container(None::<CreateContainerOptions<String>>, create_container_config)
+        .await?;
+    docker
+        .start_container(&container.id, None::<StartContainerOptions<String>>)
+        .await?;
+
+    let exit = docker
+        .wait_container(&container.id, None::<WaitContainerOptions<String>>)
+        .next()
+        .await;
+    let logs = collect_logs(&docker, &container.id).await;
+    docker
+        .remove_container(&container.id, Default::default())
+        .await?;
+
+    match exit {
+        Some(Ok(exit)) if exit.status_code == 0 => {
+            debug!("{}", logs.join(""));
+            Ok(())
+        }
+        Some(Err(e)) => Err(format!("{e}\n{}", logs.join("")).into()),
+        _ => Err(logs.join("").into()),
+    }
+}
+
+/// Keeps the base chroot up to date in the background
+pub fn start_chroot_refresh(settings: ChrootSettings) {
+    tokio::spawn(async move {
+        loop {
+            info!("Checking base chroot...");
+            match refresh_base_chroot(&settings).await {
+                Ok(()) => BASE_CHROOT_AVAILABLE.store(true, Ordering::Relaxed),
+                Err(e) => error!("Failed to refresh base chroot: {}", e),
+            }
+            sleep(Duration::from_secs(CHECK_INTERVAL)).await;
+        }
+    });
+}
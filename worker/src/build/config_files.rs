@@ -0,0 +1,121 @@
+use common::config::BuildProfile;
+use std::io;
+
+/// Path of the user `makepkg.conf` of the builder, it is sourced after `/etc/makepkg.conf`
+const MAKEPKG_CONF_PATH: &str = "build/.config/pacman/makepkg.conf";
+const PACMAN_CONF_PATH: &str = "etc/pacman.conf";
+
+/// User owning the files in its home, with the ids the build container creates it with
+const BUILDER_USER: &str = "builder";
+const BUILDER_ID: u64 = 1000;
+const DEFAULT_SIG_LEVEL: &str = "Required DatabaseOptional";
+
+/// Quotes a value for a bash assignment in `makepkg.conf`
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Renders the `pacman.conf` of a profile, `None` if the profile doesn't change it
+pub fn render_pacman_conf(profile: &BuildProfile) -> Option<String> {
+    if profile.repositories.is_none() && profile.sig_level.is_none() {
+        return None;
+    }
+    let mut conf = String::from(
+        "# Generated by the aur-builder worker\n\
+         [options]\n\
+         HoldPkg = pacman glibc\n\
+         Architecture = auto\n\
+         CheckSpace\n\
+         ParallelDownloads = 5\n",
+    );
+    conf.push_str(&format!(
+        "SigLevel = {}\nLocalFileSigLevel = Optional\n",
+        profile.sig_level.as_deref().unwrap_or(DEFAULT_SIG_LEVEL)
+    ));
+    for repo in ["core", "extra"] {
+        conf.push_str(&format!("\n[{repo}]\nInclude = /etc/pacman.d/mirrorlist\n"));
+    }
+    for repo in profile.repositories.iter().flatten() {
+        conf.push_str(&format!("\n[{}]\n", repo.name));
+        if let Some(sig_level) = &repo.sig_level {
+            conf.push_str(&format!("SigLevel = {sig_level}\n"));
+        }
+        if let Some(include) = &repo.include {
+            conf.push_str(&format!("Include = {include}\n"));
+        }
+        for server in repo.servers.iter().flatten() {
+            conf.push_str(&format!("Server = {server}\n"));
+        }
+    }
+    Some(conf)
+}
+
+/// Renders the `makepkg.conf` overrides of a profile, `None` if the profile doesn't change anything
+pub fn render_makepkg_conf(profile: &BuildProfile) -> Option<String> {
+    let variables = [
+        ("CFLAGS", &profile.cflags),
+        ("CXXFLAGS", &profile.cxxflags),
+        ("LDFLAGS", &profile.ldflags),
+        ("RUSTFLAGS", &profile.rustflags),
+        ("PKGEXT", &profile.pkgext),
+    ];
+    let mut conf = String::new();
+    for (name, value) in variables {
+        if let Some(value) = value {
+            conf.push_str(&format!("{name}={}\n", shell_quote(value)));
+        }
+    }
+    if let Some(options) = &profile.options {
+        let options: Vec<String> = options.iter().map(|o| shell_quote(o)).collect();
+        conf.push_str(&format!("OPTIONS=({})\n", options.join(" ")));
+    }
+    if conf.is_empty() {
+        return None;
+    }
+    Some(format!("# Generated by the aur-builder worker\n{conf}"))
+}
+
+/// Appends a config file to the archive, owned by root or by the builder, which appends to its `makepkg.conf`
+fn append_file(builder: &mut tar::Builder<Vec<u8>>, path: &str, content: &str) -> io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(0);
+    if path.starts_with("build/") {
+        header.set_uid(BUILDER_ID);
+        header.set_gid(BUILDER_ID);
+        header.set_username(BUILDER_USER)?;
+        header.set_groupname(BUILDER_USER)?;
+    }
+    builder.append_data(&mut header, path, content.as_bytes())
+}
+
+/// Appends the `pacman.conf` of a profile to an archive extracted to `/`, if the profile changes it
+pub fn append_pacman_conf(builder: &mut tar::Builder<Vec<u8>>, profile: &BuildProfile) -> io::Result<()> {
+    match render_pacman_conf(profile) {
+        Some(conf) => append_file(builder, PACMAN_CONF_PATH, &conf),
+        None => Ok(()),
+    }
+}
+
+/// Creates a tar archive with the rendered config files of a profile, relative to `/`.
+///
+/// Returns `None` if the profile doesn't change any config file.
+pub fn config_archive(profile: &BuildProfile) -> io::Result<Option<Vec<u8>>> {
+    let files: Vec<(&str, String)> = [
+        (PACMAN_CONF_PATH, render_pacman_conf(profile)),
+        (MAKEPKG_CONF_PATH, render_makepkg_conf(profile)),
+    ]
+    .into_iter()
+    .filter_map(|(path, content)| content.map(|c| (path, c)))
+    .collect();
+    if files.is_empty() {
+        return Ok(None);
+    }
+
+    let mut builder = tar::Builder::new(Vec::new());
+    for (path, content) in files {
+        append_file(&mut builder, path, &content)?;
+    }
+    Ok(Some(builder.into_inner()?))
+}
@@ -0,0 +1,145 @@
+use bollard::Docker;
+use bollard::container::{Config, CreateContainerOptions, RemoveContainerOptions, StartContainerOptions};
+use bollard::models::{HostConfig, PortBinding, RestartPolicy, RestartPolicyNameEnum};
+use crate::build::docker::get_image_name;
+use common::config::{DistccSettings, WorkerConfig};
+use common::types::{BuildTaskTransmissionFormat, DistccHelper};
+use std::collections::HashMap;
+use std::thread::available_parallelism;
+
+const DISTCC_PORT: u16 = 3632;
+const HELPER_CONTAINER: &str = "aur-builder-distccd";
+const DEFAULT_ALLOW: [&str; 3] = ["10.0.0.0/8", "172.16.0.0/12", "192.168.0.0/16"];
+
+fn helper_jobs(settings: &DistccSettings) -> u32 {
+    settings
+        .jobs
+        .unwrap_or(available_parallelism().map(|n| n.get() as u32).unwrap_or(1))
+}
+
+/// Returns the helper this worker advertises in its heartbeats
+pub fn distcc_helper(config: &WorkerConfig) -> Option<DistccHelper> {
+    config.distcc.as_ref().map(|settings| DistccHelper {
+        address: format!("{}:{}", settings.address, settings.port.unwrap_or(DISTCC_PORT)),
+        jobs: helper_jobs(settings),
+    })
+}
+
+/// (Re)starts the distcc daemon in a container of the builder image, so it has the same compilers as the builds
+pub async fn start_distcc_helper(settings: &DistccSettings) -> Result<(), Box<dyn std::error::Error>> {
+    let docker = Docker::connect_with_local_defaults()?;
+    let _ = docker
+        .remove_container(
+            HELPER_CONTAINER,
+            Some(RemoveContainerOptions {
+                force: true,
+                ..Default::default()
+            }),
+        )
+        .await;
+
+    let mut cmd = vec![
+        "distccd".to_string(),
+        "--daemon".to_string(),
+        "--no-detach".to_string(),
+        "--log-stderr".to_string(),
+        "--port".to_string(),
+        DISTCC_PORT.to_string(),
+        "--jobs".to_string(),
+        helper_jobs(settings).to_string(),
+    ];
+    let allow = settings
+        .allow
+        .clone()
+        .unwrap_or(DEFAULT_ALLOW.iter().map(|a| a.to_string()).collect());
+    for network in allow {
+        cmd.push("--allow".to_string());
+        cmd.push(network);
+    }
+
+    let port = format!("{DISTCC_PORT}/tcp");
+    let config = Config {
+        image: Some(get_image_name()),
+        user: Some("builder".to_string()),
+        cmd: Some(cmd),
+        exposed_ports: Some(HashMap::from([(port.clone(), HashMap::new())])),
+        host_config: Some(HostConfig {
+            port_bindings: Some(HashMap::from([(
+                port,
+                Some(vec![PortBinding {
+                    host_ip: None,
+                    host_port: Some(settings.port.unwrap_or(DISTCC_PORT).to_string()),
+                }]),
+            )])),
+            restart_policy: Some(RestartPolicy {
+                name: Some(RestartPolicyNameEnum::UNLESS_STOPPED),
+                maximum_retry_count: None,
+            }),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    docker
+        .create_container(
+            Some(CreateContainerOptions {
+                name: HELPER_CONTAINER,
+                ..Default::default()
+            }),
+            config,
+        )
+        .await?;
+    docker
+        .start_container(HELPER_CONTAINER, None::<StartContainerOptions<String>>)
+        .await?;
+    Ok(())
+}
+
+/// Replaces the job count of `MAKEFLAGS` with `jobs`, keeping its other flags
+fn set_make_jobs(makeflags: Option<&str>, jobs: u32) -> String {
+    let mut flags = Vec::new();
+    let mut words = makeflags.unwrap_or_default().split_whitespace().peekable();
+    while let Some(word) = words.next() {
+        if word == "-j" || word == "--jobs" {
+            // the count is optional and may be the next word
+            words.next_if(|next| next.parse::<u32>().is_ok());
+        } else if !(word.starts_with("--jobs=") || word.strip_prefix("-j").is_some_and(|n| n.parse::<u32>().is_ok())) {
+            flags.push(word);
+        }
+    }
+    let jobs = format!("-j{jobs}");
+    flags.push(&jobs);
+    flags.join(" ")
+}
+
+/// Returns the environment distributing the compile jobs of a build to the helpers in the task.
+///
+/// The helper of this worker is left out, its CPUs are already used through `localhost`.
+/// The job count in `makeflags` is raised to cover the helpers, its other flags are kept.
+pub fn distcc_env(
+    task: &BuildTaskTransmissionFormat,
+    config: &WorkerConfig,
+    local_jobs: u32,
+    makeflags: Option<&str>,
+) -> Vec<String> {
+    let own_address = distcc_helper(config).map(|h| h.address);
+    let hosts: Vec<&String> = task
+        .distcc_hosts
+        .iter()
+        .flatten()
+        .filter(|spec| Some(spec.split('/').next().unwrap_or(spec)) != own_address.as_deref())
+        .collect();
+    if hosts.is_empty() {
+        return Vec::new();
+    }
+
+    let remote_jobs: u32 = hosts
+        .iter()
+        .filter_map(|spec| spec.rsplit_once('/').and_then(|(_, jobs)| jobs.parse::<u32>().ok()))
+        .sum();
+    let mut specs: Vec<String> = hosts.into_iter().cloned().collect();
+    specs.push(format!("localhost/{local_jobs}"));
+    vec![
+        format!("DISTCC_HOSTS={}", specs.join(" ")),
+        format!("MAKEFLAGS={}", set_make_jobs(makeflags, remote_jobs + local_jobs)),
+    ]
+}
@@ -1,23 +1,77 @@
 use bollard::Docker;
 use bollard::container::{
-    Config, CreateContainerOptions, LogOutput, LogsOptions, StartContainerOptions,
+    Config, CreateContainerOptions, DownloadFromContainerOptions, LogOutput, LogsOptions,
+    RemoveContainerOptions, StartContainerOptions, StopContainerOptions, UploadToContainerOptions,
     WaitContainerOptions,
 };
 use bollard::errors::Error;
 use bollard::image::CreateImageOptions;
 use bollard::models::HostConfig;
 use bytes::Bytes;
-use common::config::{Configurable, WorkerConfig};
-use common::get_rand_string;
-use common::types::{BuildResultTransmissionFormat, BuildTaskTransmissionFormat, Timestamps};
+use crate::build::cache::add_cache_mounts;
+use crate::build::chroot::{BASE_CHROOT_ARCHIVE, CHROOT_CACHE_MOUNT, allow_nspawn, base_chroot_available};
+use crate::build::config_files::config_archive;
+use crate::build::distcc::distcc_env;
+use common::config::{BuildProfile, Configurable, LocalRepoSettings, WorkerConfig};
+use common::errors::{BUILD_TIMEOUT_CODE, EXTRACTION_FAILED_CODE};
+use common::routing::{declare_log_stream, log_stream_name};
+use common::types::{
+    BuildReport, BuildResultTransmissionFormat, BuildTaskTransmissionFormat,
+    LogChunkTransmissionFormat, Timestamps,
+};
+use builder_agent::report::REPORT_PATH;
 use futures_util::{StreamExt, TryStreamExt};
+use lapin::options::BasicPublishOptions;
+use lapin::{BasicProperties, Channel};
 use sea_orm::sqlx::types::chrono::Utc;
+use std::collections::HashMap;
 use std::env;
+use std::io::Read;
+use std::path::Path;
+use tokio::io::AsyncWriteExt;
+use tar::Archive;
+use std::time::Duration;
+use tokio::time::timeout;
 
 const IMAGE: &str = "ghcr.io/neferin12/aur-builder-build-container";
 const VERSION: &str = env!("CARGO_PKG_VERSION");
+const CPU_PERIOD: i64 = 100000;
+const RESULTS_DIR: &str = "/results";
+
+/// Home directory of the builder user in the build container
+const BUILDER_HOME: &str = "/build";
+
+/// Mount point of the local repository directory inside the build container
+const LOCAL_REPO_MOUNT: &str = "/local-repo";
+const DEFAULT_LOCAL_REPO_SIGLEVEL: &str = "Optional TrustAll";
+
+/// Returns the environment adding the local repository to a container, binding its directory if it has one
+pub fn local_repo_env(local_repo: &LocalRepoSettings, binds: &mut Vec<String>) -> Vec<String> {
+    let server = match (&local_repo.path, &local_repo.url) {
+        (Some(path), _) => {
+            binds.push(format!("{path}:{LOCAL_REPO_MOUNT}:ro"));
+            format!("file://{LOCAL_REPO_MOUNT}")
+        }
+        (None, Some(url)) => url.clone(),
+        (None, None) => {
+            warn!("Local repository {} has neither a path nor a URL", local_repo.name);
+            return Vec::new();
+        }
+    };
+    vec![
+        format!("AB_LOCAL_REPO={}", local_repo.name),
+        format!("AB_LOCAL_REPO_SERVER={server}"),
+        format!(
+            "AB_LOCAL_REPO_SIGLEVEL={}",
+            local_repo
+                .sig_level
+                .clone()
+                .unwrap_or(DEFAULT_LOCAL_REPO_SIGLEVEL.to_string())
+        ),
+    ]
+}
 
-fn get_image_name() -> String {
+pub fn get_image_name() -> String {
     let config = WorkerConfig::new(env::var("AB_CONFIG_PATH").ok()).unwrap();
     format!(
         "{}:{}",
@@ -32,10 +86,15 @@ fn get_image_name() -> String {
 
 pub async fn pull_docker_image() -> Result<(), Box<dyn std::error::Error>> {
     info!("Pulling docker image...");
+    pull_image(&get_image_name()).await?;
+    info!("Image pulled successfully!");
+    Ok(())
+}
+
+pub async fn pull_image(image: &str) -> Result<(), Box<dyn std::error::Error>> {
     let docker = Docker::connect_with_local_defaults()?;
-    let image = get_image_name();
     let options = Some(CreateImageOptions {
-        from_image: image.as_str(),
+        from_image: image,
         ..Default::default()
     });
     let pull_stream = docker.create_image(options, None, None);
@@ -45,16 +104,51 @@ pub async fn pull_docker_image() -> Result<(), Box<dyn std::error::Error>> {
             Ok(())
         })
         .await?;
-
-    info!("Image pulled successfully!");
     Ok(())
 }
 
+pub fn get_build_profile(config: &WorkerConfig, name: &Option<String>) -> BuildProfile {
+    let Some(name) = name else {
+        return BuildProfile::default();
+    };
+    match config.profiles.as_ref().and_then(|p| p.get(name)) {
+        Some(profile) => profile.clone(),
+        None => {
+            warn!("Build profile '{name}' is not configured on this worker, using defaults");
+            BuildProfile::default()
+        }
+    }
+}
+
+fn mib_to_bytes(mib: u64) -> i64 {
+    (mib * 1024 * 1024) as i64
+}
+
 fn log_message_to_string(prefix: &str, message: Bytes) -> String {
     format!("{prefix}: {}", String::from_utf8_lossy(&message))
 }
 
-fn attach_logs(docker_for_logs: Docker, container_id_for_logs: String) {
+async fn publish_log_chunk(log_channel: &Channel, chunk: &LogChunkTransmissionFormat) {
+    let published = log_channel
+        .basic_publish(
+            "",
+            &log_stream_name(&chunk.build_id),
+            BasicPublishOptions::default(),
+            serde_json::to_string(chunk).unwrap().as_ref(),
+            BasicProperties::default(),
+        )
+        .await;
+    if let Err(e) = published {
+        warn!("Failed to publish log chunk of build {}: {}", chunk.build_id, e);
+    }
+}
+
+fn attach_logs(
+    docker_for_logs: Docker,
+    container_id_for_logs: String,
+    log_channel: Channel,
+    build_id: String,
+) {
     tokio::spawn(async move {
         debug!("Attaching to logs...");
         let mut logs_stream = docker_for_logs.logs(
@@ -68,39 +162,151 @@ fn attach_logs(docker_for_logs: Docker, container_id_for_logs: String) {
             }),
         );
 
+        let mut sequence = 0;
         while let Some(log_result) = logs_stream.next().await {
-            match log_result {
-                Ok(LogOutput::StdOut { message }) => {
-                    debug!("{}", log_message_to_string("stdout", message));
-                }
-                Ok(LogOutput::StdErr { message }) => {
-                    debug!("{}", log_message_to_string("stderr", message));
-                }
+            let line = match log_result {
+                Ok(LogOutput::StdOut { message }) => log_message_to_string("stdout", message),
+                Ok(LogOutput::StdErr { message }) => log_message_to_string("stderr", message),
                 Err(e) => {
                     error!("Error reading log stream: {}", e);
                     break;
                 }
-                _ => {}
-            }
+                _ => continue,
+            };
+            debug!("{}", line);
+            let chunk = LogChunkTransmissionFormat {
+                build_id: build_id.clone(),
+                sequence,
+                lines: vec![line],
+                finished: false,
+            };
+            publish_log_chunk(&log_channel, &chunk).await;
+            sequence += 1;
         }
+
+        let chunk = LogChunkTransmissionFormat {
+            build_id,
+            sequence,
+            lines: Vec::new(),
+            finished: true,
+        };
+        publish_log_chunk(&log_channel, &chunk).await;
     });
 }
 
+pub async fn collect_logs(docker: &Docker, container_id: &str) -> Vec<String> {
+    let mut logs = docker.logs(
+        container_id,
+        Some(LogsOptions::<String> {
+            stdout: true,
+            stderr: true,
+            // tail: "all",  // optional
+            ..Default::default()
+        }),
+    );
+    let mut logs_vec = Vec::new();
+
+    while let Some(log_result) = logs.next().await {
+        match log_result {
+            Ok(LogOutput::StdOut { message }) => {
+                logs_vec.push(log_message_to_string("stdout", message));
+            }
+            Ok(LogOutput::StdErr { message }) => {
+                logs_vec.push(log_message_to_string("stderr", message));
+            }
+            Err(e) => {
+                error!("Error reading log stream: {}", e);
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    logs_vec
+}
+
+/// Downloads a single file from a container, the Docker API returns it wrapped in a tar archive
+async fn read_file_from_container(
+    docker: &Docker,
+    container_id: &str,
+    path: &str,
+) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+    let archive = docker
+        .download_from_container(container_id, Some(DownloadFromContainerOptions { path }))
+        .try_fold(Vec::new(), |mut archive, chunk| async move {
+            archive.extend_from_slice(&chunk);
+            Ok(archive)
+        })
+        .await?;
+
+    let mut archive = Archive::new(archive.as_slice());
+    let mut entry = archive.entries()?.next().ok_or("Empty archive")??;
+    let mut content = Vec::new();
+    entry.read_to_end(&mut content)?;
+    Ok(content)
+}
+
+/// Copies the `/results` directory out of a container, it's placed at `target_dir/results`
+async fn download_results(
+    docker: &Docker,
+    container_id: &str,
+    target_dir: &Path,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let archive_path = target_dir.join("results.tar");
+    let mut archive_file = tokio::fs::File::create(&archive_path).await?;
+    let mut stream = docker.download_from_container(
+        container_id,
+        Some(DownloadFromContainerOptions { path: RESULTS_DIR }),
+    );
+    while let Some(chunk) = stream.next().await {
+        archive_file.write_all(&chunk?).await?;
+    }
+    archive_file.flush().await?;
+
+    let target_dir = target_dir.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let mut archive = Archive::new(std::fs::File::open(&archive_path)?);
+        archive.unpack(&target_dir)?;
+        std::fs::remove_file(&archive_path)
+    })
+    .await??;
+    Ok(())
+}
+
+async fn fetch_report(docker: &Docker, container_id: &str) -> Option<BuildReport> {
+    let content = match read_file_from_container(docker, container_id, REPORT_PATH).await {
+        Ok(c) => c,
+        Err(e) => {
+            warn!("Failed to read build report: {}", e);
+            return None;
+        }
+    };
+    match serde_json::from_slice(&content) {
+        Ok(report) => Some(report),
+        Err(e) => {
+            warn!("Build report is invalid: {}", e);
+            None
+        }
+    }
+}
+
 pub async fn build(
     task: &BuildTaskTransmissionFormat,
+    build_id: &str,
+    log_channel: &Channel,
     source_url: String,
     subfolder: &Option<String>,
-) -> Result<BuildResultTransmissionFormat, Box<dyn std::error::Error>> {
-    match pull_docker_image().await {
-        Err(_) => warn!("Builder image could not be pulled"),
-        _ => {}
+    results_dir: &Path,
+) -> Result<BuildResultTransmissionFormat, Box<dyn std::error::Error + Send + Sync>> {
+    if pull_docker_image().await.is_err() {
+        warn!("Builder image could not be pulled");
     }
     info!("Building package {}", task.name);
     let config = WorkerConfig::new(env::var("AB_CONFIG_PATH").ok()).unwrap();
     let build_start_time = Utc::now().naive_utc();
 
     let docker = Docker::connect_with_local_defaults()?;
-    let container_name = format!("build-{}-{}", task.name, get_rand_string());
+    let container_name = format!("build-{build_id}");
     let create_container_options = CreateContainerOptions {
         name: container_name,
         ..Default::default()
@@ -112,34 +318,112 @@ pub async fn build(
             "AB_SUBFOLDER={}",
             subfolder.clone().unwrap_or("".to_string())
         ),
-        format!("AB_GITEA_REPO={}", config.gitea.repo),
-        format!("AB_GITEA_USER={}", config.gitea.user),
-        format!("AB_GITEA_TOKEN={}", config.gitea.token),
         format!(
             "AB_OPTIONS={}",
             task.clone().options.unwrap_or("".to_string())
         ),
     ];
 
+    if let Some(allowed) = &task.namcap_allow {
+        env.push(format!("AB_NAMCAP_ALLOW={}", allowed.join(",")));
+    }
+
+    let profile = get_build_profile(&config, &task.profile);
+    // only recorded if this worker knows the profile
+    let applied_profile = task
+        .profile
+        .clone()
+        .filter(|name| config.profiles.as_ref().is_some_and(|p| p.contains_key(name)));
+
+    if let Some(makeflags) = &profile.makeflags {
+        env.push(format!("MAKEFLAGS={makeflags}"));
+    }
+
     if let Some(task_env) = task.env.clone() {
         for env_var in task_env {
             env.push(format!("{}={}", env_var.name, env_var.value));
         }
     }
 
+    let mut binds = Vec::new();
+    if let Some(local_repo) = &config.local_repo {
+        env.extend(local_repo_env(local_repo, &mut binds));
+    }
+
+    if task.cache.unwrap_or(true)
+        && let Some(cache) = &config.cache
+    {
+        add_cache_mounts(cache, &mut env, &mut binds);
+    }
+
+    let mut nspawn = false;
+    if profile.clean_chroot.unwrap_or(false) {
+        match &config.chroot {
+            Some(chroot) if base_chroot_available() => {
+                binds.push(format!("{}:{CHROOT_CACHE_MOUNT}:ro", chroot.path));
+                env.push(format!("AB_CHROOT_ARCHIVE={CHROOT_CACHE_MOUNT}/{BASE_CHROOT_ARCHIVE}"));
+                nspawn = true;
+            }
+            Some(_) => warn!("No base chroot is available yet, building {} without a clean chroot", task.name),
+            None => warn!("No chroot cache is configured, building {} without a clean chroot", task.name),
+        }
+    }
+
+    if let Some(verify) = &task.verify {
+        env.push(format!("AB_VERIFY_FILES={}", verify.files.join(",")));
+        // makerepropkg builds in a systemd-nspawn chroot as well
+        nspawn = true;
+    }
+
     let image = get_image_name();
 
+    let resources = task
+        .resources
+        .clone()
+        .unwrap_or_default()
+        .or(&profile.resources.clone().unwrap_or_default())
+        .or(&config.slot_resources.clone().unwrap_or_default());
+
+    let local_jobs = resources.cpus.unwrap_or(1.0).ceil().max(1.0) as u32;
+    let distcc = distcc_env(task, &config, local_jobs, profile.makeflags.as_deref());
+    if !distcc.is_empty() {
+        // replaced by the profile's flags with a job count covering the helpers
+        env.retain(|e| !e.starts_with("MAKEFLAGS="));
+        env.extend(distcc);
+    }
+
+    let tmpfs = resources
+        .tmpfs_size
+        .map(|size| HashMap::from([("/tmp".to_string(), format!("size={size}m"))]));
+
+    let mut host_config = HostConfig {
+        // e.g., to remove container automatically upon exit:
+        auto_remove: Some(false),
+        cpu_period: Some(CPU_PERIOD),
+        cpu_quota: Some((resources.cpus.unwrap_or(1.0) * CPU_PERIOD as f64) as i64),
+        memory: resources.memory.map(mib_to_bytes),
+        memory_swap: resources
+            .memory
+            .zip(resources.swap)
+            .map(|(memory, swap)| mib_to_bytes(memory + swap)),
+        pids_limit: resources.pids_limit,
+        tmpfs,
+        binds: Some(binds),
+        ..Default::default()
+    };
+    let mut user = "builder";
+    if nspawn {
+        allow_nspawn(&mut host_config);
+        // the agent drops the sudo rights of builder and runs the package code as builder itself
+        user = "root";
+        env.push(format!("HOME={BUILDER_HOME}"));
+    }
+
     let create_container_config = Config {
         image: Some(image),
-        user: Some("builder".to_string()),
+        user: Some(user.to_string()),
         env: Some(env),
-        host_config: Some(HostConfig {
-            // e.g., to remove container automatically upon exit:
-            auto_remove: Some(false),
-            cpu_period: Some(100000),
-            cpu_quota: Some(100000),
-            ..Default::default()
-        }),
+        host_config: Some(host_config),
         ..Default::default()
     };
 
@@ -147,81 +431,134 @@ pub async fn build(
         .create_container(Some(create_container_options), create_container_config)
         .await?;
 
-    docker
-        .start_container(&container.id, None::<StartContainerOptions<String>>)
-        .await?;
-
-    attach_logs(docker.clone(), container.id.clone());
-
-    let mut wait_stream =
-        docker.wait_container(&container.id, None::<WaitContainerOptions<String>>);
-    while let Some(res) = wait_stream.next().await {
-        let mut logs = docker.logs(
-            &container.id,
-            Some(LogsOptions::<String> {
-                stdout: true,
-                stderr: true,
-                // tail: "all",  // optional
-                ..Default::default()
-            }),
-        );
-        let mut logs_vec = Vec::new();
+    // every step after creating the container is fallible, but the container must be removed anyway
+    let outcome = async {
+        if let Some(archive) = config_archive(&profile)? {
+            docker
+                .upload_to_container(
+                    &container.id,
+                    Some(UploadToContainerOptions {
+                        path: "/",
+                        ..Default::default()
+                    }),
+                    Bytes::from(archive),
+                )
+                .await?;
+        }
 
-        while let Some(log_result) = logs.next().await {
-            match log_result {
-                Ok(LogOutput::StdOut { message }) => {
-                    logs_vec.push(log_message_to_string("stdout", message));
-                }
-                Ok(LogOutput::StdErr { message }) => {
-                    logs_vec.push(log_message_to_string("stderr", message));
-                }
-                Err(e) => {
-                    error!("Error reading log stream: {}", e);
-                    break;
-                }
-                _ => {}
-            }
+        if let Err(e) = declare_log_stream(log_channel, build_id).await {
+            warn!("Failed to declare log stream of build {build_id}: {e}");
         }
 
         docker
-            .remove_container(&container.id, Default::default())
+            .start_container(&container.id, None::<StartContainerOptions<String>>)
             .await?;
 
-        let build_end_time = Utc::now().naive_utc();
-
-        let mut results = BuildResultTransmissionFormat {
-            task: task.to_owned(),
-            status_code: -5,
-            log_lines: logs_vec,
-            success: true,
-            timestamps: Timestamps {
-                start: build_start_time,
-                end: build_end_time,
-            },
+        attach_logs(
+            docker.clone(),
+            container.id.clone(),
+            log_channel.clone(),
+            build_id.to_string(),
+        );
+
+        let build_timeout = task
+            .timeout
+            .or(config.build_timeout)
+            .map(Duration::from_secs);
+
+        let mut wait_stream =
+            docker.wait_container(&container.id, None::<WaitContainerOptions<String>>);
+        let wait_result = match build_timeout {
+            Some(duration) => timeout(duration, wait_stream.next()).await.ok(),
+            None => Some(wait_stream.next().await),
         };
 
-        return match res {
-            Ok(exit) => {
-                info!("Build container exited with: {:?}", exit.status_code);
+        if wait_result.is_none() {
+            warn!(
+                "Build of package {} timed out after {}s, stopping container",
+                task.name,
+                build_timeout.unwrap_or_default().as_secs()
+            );
+            docker
+                .stop_container(&container.id, Some(StopContainerOptions { t: 10 }))
+                .await?;
+        }
+
+        let logs_vec = collect_logs(&docker, &container.id).await;
+        let report = fetch_report(&docker, &container.id).await;
+
+        let mut extraction_failed = false;
+        if let Some(Some(Ok(exit))) = &wait_result
+            && exit.status_code == 0
+            && let Err(e) = download_results(&docker, &container.id, results_dir).await
+        {
+            error!("Failed to extract results of build {build_id}: {}", e);
+            extraction_failed = true;
+        }
+        Ok::<_, Box<dyn std::error::Error + Send + Sync>>((wait_result, logs_vec, report, extraction_failed))
+    }
+    .await;
+    // also removes a container that is still running after an error
+    let removed = docker
+        .remove_container(
+            &container.id,
+            Some(RemoveContainerOptions {
+                force: true,
+                ..Default::default()
+            }),
+        )
+        .await;
+    if let Err(e) = removed {
+        warn!("Failed to remove build container {}: {}", container.id, e);
+    }
+    let (wait_result, logs_vec, report, extraction_failed) = outcome?;
+
+    let build_end_time = Utc::now().naive_utc();
+
+    let mut results = BuildResultTransmissionFormat {
+        task: task.to_owned(),
+        build_id: build_id.to_string(),
+        status_code: -5,
+        log_lines: logs_vec,
+        success: true,
+        timestamps: Timestamps {
+            start: build_start_time,
+            end: build_end_time,
+        },
+        report,
+        uploads: Vec::new(),
+        signing_key: None,
+        profile: applied_profile,
+        smoke_test: None,
+        packages: Vec::new(),
+    };
+
+    match wait_result {
+        None => {
+            results.status_code = BUILD_TIMEOUT_CODE;
+            results.success = false;
+            Ok(results)
+        }
+        Some(None) => Err("Unexpected end of wait stream".into()),
+        Some(Some(Ok(_))) if extraction_failed => {
+            results.status_code = EXTRACTION_FAILED_CODE;
+            results.success = false;
+            Ok(results)
+        }
+        Some(Some(Ok(exit))) => {
+            info!("Build container exited with: {:?}", exit.status_code);
 
-                results.status_code = exit.status_code;
+            results.status_code = exit.status_code;
 
+            Ok(results)
+        }
+        Some(Some(Err(e))) => match e {
+            Error::DockerContainerWaitError { code, .. } => {
+                results.status_code = code;
+                results.success = false;
                 Ok(results)
             }
-            Err(e) => {
-                match e {
-                    Error::DockerContainerWaitError { code, .. } => {
-                        results.status_code = code;
-                        results.success = false;
-                        Ok(results)
-                    }
-                    _ => Err(e.into()),
-                }
-                // error!("Error while waiting for build container: {:?}", e);
-                // return Err(results);
-            }
-        };
+            _ => Err(e.into()),
+        },
     }
-
-    Err("Unexpected end of wait stream".into())
 }
@@ -0,0 +1,91 @@
+use common::types::{BuildInfo, BuildResultTransmissionFormat, PackageInfo};
+use repo_db::package::Package;
+use std::path::{Path, PathBuf};
+
+/// Returns every value of a key of a `.BUILDINFO`, which lists one `key = value` pair per line
+fn buildinfo_values<'a>(buildinfo: &'a str, key: &str) -> Vec<&'a str> {
+    buildinfo
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once(" = "))
+        .filter(|(k, _)| *k == key)
+        .map(|(_, value)| value)
+        .collect()
+}
+
+fn parse_buildinfo(buildinfo: &str) -> BuildInfo {
+    let values = |key: &str| {
+        buildinfo_values(buildinfo, key)
+            .into_iter()
+            .map(str::to_string)
+            .collect::<Vec<String>>()
+    };
+    let first = |key: &str| buildinfo_values(buildinfo, key).first().map(|v| v.to_string());
+    BuildInfo {
+        packager: first("packager").unwrap_or_default(),
+        build_date: first("builddate").and_then(|d| d.parse().ok()).unwrap_or_default(),
+        build_tool: first("buildtool").map(|tool| match first("buildtoolver") {
+            Some(version) => format!("{tool} {version}"),
+            None => tool,
+        }),
+        build_env: values("buildenv"),
+        options: values("options"),
+        installed: values("installed"),
+    }
+}
+
+/// Reads the `.PKGINFO`, the `.BUILDINFO` and the file list of a package file
+fn read_package_info(path: &Path) -> Result<PackageInfo, repo_db::RepoError> {
+    let Package {
+        desc,
+        files,
+        buildinfo,
+    } = Package::read(path)?;
+    let values = |section: &str| desc.get(section).map(<[String]>::to_vec).unwrap_or_default();
+    Ok(PackageInfo {
+        file: desc.filename().to_string(),
+        name: desc.name().to_string(),
+        version: desc.version().to_string(),
+        arch: desc.first("ARCH").unwrap_or_default().to_string(),
+        description: desc.first("DESC").map(str::to_string),
+        url: desc.first("URL").map(str::to_string),
+        size: desc.first("ISIZE").and_then(|s| s.parse().ok()).unwrap_or_default(),
+        licenses: values("LICENSE"),
+        depends: values("DEPENDS"),
+        optdepends: values("OPTDEPENDS"),
+        provides: values("PROVIDES"),
+        conflicts: values("CONFLICTS"),
+        replaces: values("REPLACES"),
+        build_info: buildinfo.as_deref().map(parse_buildinfo),
+        files,
+    })
+}
+
+/// Adds the metadata of the built package files in `results_dir` to `results.packages`.
+///
+/// Packages that can't be read are skipped, their metadata is informational only.
+pub async fn collect_package_info(results: &mut BuildResultTransmissionFormat, results_dir: &Path) {
+    let files: Vec<PathBuf> = results
+        .report
+        .iter()
+        .flat_map(|r| &r.produced_files)
+        .map(|f| results_dir.join(&f.name))
+        .collect();
+    let packages = tokio::task::spawn_blocking(move || {
+        files
+            .iter()
+            .filter_map(|file| match read_package_info(file) {
+                Ok(info) => Some(info),
+                Err(e) => {
+                    warn!("Failed to read the metadata of {}: {}", file.display(), e);
+                    None
+                }
+            })
+            .collect()
+    })
+    .await;
+    match packages {
+        Ok(packages) => results.packages = packages,
+        Err(e) => warn!("Failed to read the metadata of the built packages: {}", e),
+    }
+}
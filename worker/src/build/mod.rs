@@ -1,13 +1,85 @@
 use common::types::{BuildResultTransmissionFormat, BuildTaskTransmissionFormat};
+use crate::build::artifacts::publish_artifacts;
+use crate::publish::get_publishers;
+use crate::sign::GpgSigner;
+use common::config::{Configurable, WorkerConfig};
+use common::errors::{SIGNING_FAILED_CODE, UPLOAD_FAILED_CODE};
+use lapin::Channel;
+use std::env;
+use std::sync::Arc;
 
+pub mod artifacts;
+pub mod cache;
+pub mod chroot;
+pub mod config_files;
+pub mod distcc;
 pub mod docker;
+pub mod metadata;
+pub mod smoke_test;
 
-pub async fn build_package(task: &BuildTaskTransmissionFormat) -> Result<BuildResultTransmissionFormat, Box<dyn std::error::Error>> {
+pub async fn build_package(task: &BuildTaskTransmissionFormat, build_id: &str, log_channel: &Channel) -> Result<BuildResultTransmissionFormat, Box<dyn std::error::Error + Send + Sync>> {
     let source_url = match &task.source {
         None => { format!("https://aur.archlinux.org/{}.git", task.name) }
         Some(s) => { s.to_owned() }
     };
     
-    docker::build(task, source_url, &task.subfolder).await
+    let config = WorkerConfig::new(env::var("AB_CONFIG_PATH").ok()).unwrap();
+    let results_dir = tempfile::tempdir()?;
+
+    let cache_lock = match &config.cache {
+        Some(cache) => cache::lock_shared(cache).await,
+        None => None,
+    };
+    let mut results = docker::build(
+        task,
+        build_id,
+        log_channel,
+        source_url,
+        &task.subfolder,
+        results_dir.path(),
+    )
+    .await?;
+    drop(cache_lock);
+
+    if let Some(cache) = config.cache.clone()
+        && let Some(max_size) = cache.max_size
+    {
+        cache::schedule_eviction(cache, max_size);
+    }
+
+    // a reproducibility check only compares the rebuilt packages with the published ones
+    if task.verify.is_some() {
+        return Ok(results);
+    }
+
+    if results.success && results.status_code == 0 {
+        metadata::collect_package_info(&mut results, &results_dir.path().join("results")).await;
+
+        // failing packages are never published
+        if let Some(command) = &task.smoke_test
+            && !smoke_test::run_smoke_test(&mut results, command, &config, &results_dir.path().join("results")).await
+        {
+            return Ok(results);
+        }
+
+        let signer = match config.signing.clone() {
+            None => None,
+            Some(settings) => match tokio::task::spawn_blocking(move || GpgSigner::new(&settings)).await? {
+                Ok(signer) => Some(Arc::new(signer)),
+                Err(e) => {
+                    artifacts::fail(&mut results, SIGNING_FAILED_CODE, e);
+                    return Ok(results);
+                }
+            },
+        };
+        match get_publishers(&config, &task.publish_to, signer.clone()) {
+            Ok(publishers) => {
+                publish_artifacts(&mut results, &results_dir.path().join("results"), &publishers, signer).await
+            }
+            Err(e) => artifacts::fail(&mut results, UPLOAD_FAILED_CODE, e),
+        }
+    }
+
+    Ok(results)
 
 }
\ No newline at end of file
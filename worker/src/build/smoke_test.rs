@@ -0,0 +1,198 @@
+use bollard::Docker;
+use bollard::container::{
+    Config, CreateContainerOptions, RemoveContainerOptions, StartContainerOptions,
+    StopContainerOptions, UploadToContainerOptions, WaitContainerOptions,
+};
+use bollard::models::HostConfig;
+use bytes::Bytes;
+use crate::build::artifacts::{fail, now_millis};
+use crate::build::config_files::append_pacman_conf;
+use crate::build::docker::{collect_logs, get_build_profile, local_repo_env, pull_image};
+use common::config::{BuildProfile, SmokeTestSettings, WorkerConfig};
+use common::errors::SMOKE_TEST_FAILED_CODE;
+use common::types::{BuildResultTransmissionFormat, Phase, PhaseReport, SmokeTestResult};
+use futures_util::StreamExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::time::timeout;
+
+const DEFAULT_IMAGE: &str = "archlinux:latest";
+const DEFAULT_TIMEOUT: u64 = 600;
+const PACKAGES_DIR: &str = "/packages";
+
+/// Adds the local repository to the `pacman.conf` of the profile, installs the uploaded packages with their dependencies and runs the command
+const TEST_SCRIPT: &str = r#"set -e
+if [ -n "$AB_LOCAL_REPO" ]; then
+    printf '\n[%s]\nSigLevel = %s\nServer = %s\n' "$AB_LOCAL_REPO" "$AB_LOCAL_REPO_SIGLEVEL" "$AB_LOCAL_REPO_SERVER" >> /etc/pacman.conf
+fi
+pacman -Syu --noconfirm
+pacman -U --noconfirm /packages/*
+echo "Running smoke test: $AB_SMOKE_TEST"
+bash -c "$AB_SMOKE_TEST"
+"#;
+
+/// Packs the package files into a tar archive extracted to [`PACKAGES_DIR`], with the `pacman.conf` of the profile
+/// the packages were built with, so their dependencies resolve from the same repositories
+fn packages_archive(files: &[PathBuf], profile: &BuildProfile) -> std::io::Result<Vec<u8>> {
+    let mut builder = tar::Builder::new(Vec::new());
+    append_pacman_conf(&mut builder, profile)?;
+    for file in files {
+        let name = file.file_name().unwrap_or_default();
+        builder.append_path_with_name(file, Path::new(&PACKAGES_DIR[1..]).join(name))?;
+    }
+    builder.into_inner()
+}
+
+/// Installs the packages into a fresh container and runs the command, returns its exit code and output
+async fn run_in_container(
+    settings: &SmokeTestSettings,
+    config: &WorkerConfig,
+    command: &str,
+    files: Vec<PathBuf>,
+    profile: BuildProfile,
+    build_id: &str,
+) -> Result<(i64, Vec<String>), Box<dyn std::error::Error + Send + Sync>> {
+    let image = settings.image.clone().unwrap_or(DEFAULT_IMAGE.to_string());
+    if pull_image(&image).await.is_err() {
+        warn!("Smoke test image {image} could not be pulled");
+    }
+
+    let mut env = vec![format!("AB_SMOKE_TEST={command}")];
+    let mut binds = Vec::new();
+    if let Some(local_repo) = &config.local_repo {
+        env.extend(local_repo_env(local_repo, &mut binds));
+    }
+
+    let docker = Docker::connect_with_local_defaults()?;
+    let container = docker
+        .create_container(
+            Some(CreateContainerOptions {
+                name: format!("test-{build_id}"),
+                ..Default::default()
+            }),
+            Config {
+                image: Some(image),
+                cmd: Some(vec!["bash".to_string(), "-c".to_string(), TEST_SCRIPT.to_string()]),
+                env: Some(env),
+                host_config: Some(HostConfig {
+                    binds: Some(binds),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    let outcome = run_test(&docker, &container.id, settings, files, profile).await;
+    // also removes a container that is still running after an error
+    let removed = docker
+        .remove_container(
+            &container.id,
+            Some(RemoveContainerOptions {
+                force: true,
+                ..Default::default()
+            }),
+        )
+        .await;
+    if let Err(e) = removed {
+        warn!("Failed to remove smoke test container {}: {}", container.id, e);
+    }
+    outcome
+}
+
+/// Uploads the packages to the created test container, runs it and collects its output
+async fn run_test(
+    docker: &Docker,
+    container_id: &str,
+    settings: &SmokeTestSettings,
+    files: Vec<PathBuf>,
+    profile: BuildProfile,
+) -> Result<(i64, Vec<String>), Box<dyn std::error::Error + Send + Sync>> {
+    let archive = tokio::task::spawn_blocking(move || packages_archive(&files, &profile)).await??;
+    docker
+        .upload_to_container(
+            container_id,
+            Some(UploadToContainerOptions {
+                path: "/",
+                ..Default::default()
+            }),
+            Bytes::from(archive),
+        )
+        .await?;
+    docker
+        .start_container(container_id, None::<StartContainerOptions<String>>)
+        .await?;
+
+    let test_timeout = Duration::from_secs(settings.timeout.unwrap_or(DEFAULT_TIMEOUT));
+    let mut wait_stream = docker.wait_container(container_id, None::<WaitContainerOptions<String>>);
+    let exit_code = match timeout(test_timeout, wait_stream.next()).await {
+        Ok(Some(Ok(exit))) => exit.status_code,
+        Ok(Some(Err(bollard::errors::Error::DockerContainerWaitError { code, .. }))) => code,
+        Ok(Some(Err(e))) => return Err(e.into()),
+        Ok(None) => return Err("Unexpected end of wait stream".into()),
+        Err(_) => {
+            docker
+                .stop_container(container_id, Some(StopContainerOptions { t: 10 }))
+                .await?;
+            -1
+        }
+    };
+    let mut log_lines = collect_logs(docker, container_id).await;
+    if exit_code == -1 {
+        log_lines.push(format!("stderr: worker: smoke test timed out after {}s\n", test_timeout.as_secs()));
+    }
+
+    Ok((exit_code, log_lines))
+}
+
+/// Runs the smoke test of a successful build, its outcome is added to `results.smoke_test` and the report.
+///
+/// Returns whether the packages passed, the build is marked as failed otherwise.
+pub async fn run_smoke_test(
+    results: &mut BuildResultTransmissionFormat,
+    command: &str,
+    config: &WorkerConfig,
+    results_dir: &Path,
+) -> bool {
+    let started_at = now_millis();
+    let settings = config.smoke_test.clone().unwrap_or_default();
+    let profile = get_build_profile(config, &results.task.profile);
+    let files: Vec<PathBuf> = results
+        .report
+        .iter()
+        .flat_map(|r| &r.produced_files)
+        .map(|f| results_dir.join(&f.name))
+        .collect();
+
+    let (exit_code, log_lines) = match files.is_empty() {
+        true => (-1, vec!["stderr: worker: no built packages to test\n".to_string()]),
+        false => match run_in_container(&settings, config, command, files, profile, &results.build_id).await {
+            Ok(outcome) => outcome,
+            Err(e) => (-1, vec![format!("stderr: worker: failed to run the smoke test: {e}\n")]),
+        },
+    };
+    let success = exit_code == 0;
+    results.log_lines.push(format!(
+        "stdout: worker: smoke test `{command}` finished with exit code {exit_code}\n"
+    ));
+    results.smoke_test = Some(SmokeTestResult {
+        command: command.to_string(),
+        exit_code,
+        success,
+        log_lines,
+    });
+
+    if let Some(report) = results.report.as_mut() {
+        report.phases.push(PhaseReport {
+            phase: Phase::Test,
+            started_at,
+            finished_at: now_millis(),
+            exit_status: if success { 0 } else { SMOKE_TEST_FAILED_CODE as i32 },
+            failing_item: (!success).then(|| command.to_string()),
+        });
+    }
+    if !success {
+        fail(results, SMOKE_TEST_FAILED_CODE, format!("smoke test `{command}` failed"));
+    }
+    success
+}
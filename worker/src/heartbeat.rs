@@ -0,0 +1,145 @@
+use crate::build::distcc::distcc_helper;
+use common::config::WorkerConfig;
+use common::environment::VERSION;
+use common::get_rand_string;
+use common::types::{RunningBuild, WorkerHeartbeatTransmissionFormat};
+use lapin::options::{BasicPublishOptions, QueueDeclareOptions};
+use lapin::types::FieldTable;
+use lapin::{BasicProperties, Channel};
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{self, Read, Seek, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::time::sleep;
+
+/// The builds currently running on this worker, keyed by their delivery tag
+pub type RunningBuilds = Arc<Mutex<HashMap<u64, RunningBuild>>>;
+
+const DEFAULT_HEARTBEAT_INTERVAL: u64 = 15;
+const DEFAULT_WORKER_ID_PATH: &str = "/var/lib/aur-builder/worker_id";
+
+/// Number of id files replicas sharing a volume may claim, `worker_id`, `worker_id.1`, ...
+const WORKER_ID_SLOTS: usize = 64;
+
+/// The claimed id file, locked as long as the worker runs
+static WORKER_ID_FILE: OnceLock<File> = OnceLock::new();
+
+pub fn get_hostname() -> String {
+    env::var("HOSTNAME")
+        .ok()
+        .or_else(|| {
+            fs::read_to_string("/etc/hostname")
+                .ok()
+                .map(|h| h.trim().to_string())
+        })
+        .unwrap_or("unknown".to_string())
+}
+
+/// Reads the worker id from `path`, or stores a new one there, unless another worker holds the file.
+fn claim_worker_id(path: &str) -> io::Result<Option<String>> {
+    if let Some(dir) = Path::new(path).parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+    match file.try_lock() {
+        Ok(()) => {}
+        Err(TryLockError::WouldBlock) => return Ok(None),
+        Err(TryLockError::Error(e)) => return Err(e),
+    }
+
+    let mut worker_id = String::new();
+    file.read_to_string(&mut worker_id)?;
+    let mut worker_id = worker_id.trim().to_string();
+    if worker_id.is_empty() {
+        worker_id = format!("worker-{}", get_rand_string());
+        file.set_len(0)?;
+        file.rewind()?;
+        file.write_all(worker_id.as_bytes())?;
+    }
+    WORKER_ID_FILE.set(file).ok();
+    Ok(Some(worker_id))
+}
+
+/// Returns the configured worker id, or the one generated on the first start.
+///
+/// The hostname is no stable identifier, in Docker it is the id of the container. Replicas sharing the directory of
+/// `worker_id_path` each claim a file of their own, so they keep distinct ids across restarts.
+fn get_worker_id(config: &WorkerConfig) -> String {
+    if let Some(worker_id) = &config.worker_id {
+        return worker_id.clone();
+    }
+    let path = config.worker_id_path.as_deref().unwrap_or(DEFAULT_WORKER_ID_PATH);
+    for slot in 0..WORKER_ID_SLOTS {
+        let slot_path = match slot {
+            0 => path.to_string(),
+            n => format!("{path}.{n}"),
+        };
+        match claim_worker_id(&slot_path) {
+            Ok(Some(worker_id)) => return worker_id,
+            Ok(None) => continue,
+            Err(e) => {
+                warn!("Failed to store the worker id in {slot_path}, it changes with the next start: {e}");
+                break;
+            }
+        }
+    }
+    format!("worker-{}", get_rand_string())
+}
+
+/// Declares the heartbeat queue and periodically publishes the state of this worker to it.
+pub async fn start_heartbeat(
+    channel: Channel,
+    config: &WorkerConfig,
+    capacity: usize,
+    running_builds: RunningBuilds,
+) {
+    channel
+        .queue_declare(
+            "worker_heartbeats",
+            QueueDeclareOptions::default(),
+            FieldTable::default(),
+        )
+        .await
+        .unwrap();
+
+    let hostname = get_hostname();
+    let worker_id = get_worker_id(config);
+    let labels = config.labels.clone().unwrap_or_default();
+    let distcc = distcc_helper(config);
+    let interval = Duration::from_secs(
+        config
+            .heartbeat_interval
+            .unwrap_or(DEFAULT_HEARTBEAT_INTERVAL),
+    );
+    info!("Registering as worker '{worker_id}'");
+
+    tokio::spawn(async move {
+        loop {
+            let heartbeat = WorkerHeartbeatTransmissionFormat {
+                worker_id: worker_id.clone(),
+                hostname: hostname.clone(),
+                version: VERSION.to_string(),
+                labels: labels.clone(),
+                capacity,
+                running_builds: running_builds.lock().unwrap().values().cloned().collect(),
+                distcc: distcc.clone(),
+            };
+            let published = channel
+                .basic_publish(
+                    "",
+                    "worker_heartbeats",
+                    BasicPublishOptions::default(),
+                    serde_json::to_string(&heartbeat).unwrap().as_ref(),
+                    BasicProperties::default(),
+                )
+                .await;
+            if let Err(e) = published {
+                error!("Failed to send heartbeat: {}", e);
+            }
+            sleep(interval).await;
+        }
+    });
+}
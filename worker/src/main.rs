@@ -1,15 +1,29 @@
 mod build;
+mod heartbeat;
+mod publish;
+mod sign;
 
 use crate::build::build_package;
+use crate::build::chroot::start_chroot_refresh;
+use crate::build::distcc::start_distcc_helper;
 use crate::build::docker::pull_docker_image;
 use common::environment::{load_dotenv, VERSION};
 use common::{connect_to_rabbitmq, get_rand_string};
-use futures_util::stream::StreamExt;
+use futures_util::stream::{select_all, StreamExt};
 use lapin::BasicProperties;
 use lapin::options::{BasicAckOptions, BasicConsumeOptions, BasicNackOptions, BasicPublishOptions, BasicQosOptions, QueueDeclareOptions};
 use lapin::types::FieldTable;
 use common::errors::get_error_descriptions;
-use common::types::BuildTaskTransmissionFormat;
+use common::types::{BuildTaskTransmissionFormat, RunningBuild};
+use common::config::{Configurable, WorkerConfig};
+use common::routing::{declare_build_queue, worker_routing_keys};
+use crate::heartbeat::{start_heartbeat, RunningBuilds};
+use sea_orm::sqlx::types::chrono::Utc;
+use std::collections::HashMap;
+use std::env;
+use std::process::exit;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Semaphore;
 
 #[macro_use]
 extern crate log;
@@ -21,36 +35,60 @@ async fn main() {
 
     info!("Starting Aur-Builder Worker v{VERSION}");
     
-    match pull_docker_image().await {
-        Err(_) => warn!("Builder image could not be pulled"),
-        _ => {}
+    if pull_docker_image().await.is_err() {
+        warn!("Builder image could not be pulled");
+    }
+
+    let config = WorkerConfig::new(env::var("AB_CONFIG_PATH").ok()).unwrap();
+    let concurrent_builds = config.concurrent_builds.unwrap_or(1).max(1);
+    let prefetch = config.prefetch.unwrap_or(concurrent_builds as u16);
+    info!("Running up to {concurrent_builds} builds at the same time");
+
+    if let Some(chroot) = config.chroot.clone() {
+        start_chroot_refresh(chroot);
+    }
+
+    if let Some(distcc) = &config.distcc {
+        match start_distcc_helper(distcc).await {
+            Ok(()) => info!("Offering a distcc helper at {}", distcc.address),
+            Err(e) => error!("Failed to start distcc helper: {}", e),
+        }
     }
 
     let conn = connect_to_rabbitmq().await;
 
     let rx_channel = conn.create_channel().await.unwrap();
     rx_channel
-        .basic_qos(1, BasicQosOptions::default())
-        .await
-        .unwrap();
-    rx_channel
-        .queue_declare(
-            "pkg_build",
-            QueueDeclareOptions::default(),
-            FieldTable::default(),
-        )
-        .await
-        .unwrap();
-    let mut consumer = rx_channel
-        .basic_consume(
-            "pkg_build",
-            format!("aur-builder-worker-{}", get_rand_string()).as_str(),
-            BasicConsumeOptions::default(),
-            FieldTable::default(),
-        )
+        .basic_qos(prefetch, BasicQosOptions { global: true })
         .await
         .unwrap();
 
+    let labels = config.labels.clone().unwrap_or_default();
+    info!("Worker labels: {:?}", labels);
+    let routing_keys = match worker_routing_keys(&labels) {
+        Ok(keys) => keys,
+        Err(e) => {
+            error!("Invalid labels: {}", e);
+            exit(4);
+        }
+    };
+    let consumer_tag = format!("aur-builder-worker-{}", get_rand_string());
+    let mut consumers = Vec::new();
+    for routing_key in routing_keys {
+        declare_build_queue(&rx_channel, &routing_key).await.unwrap();
+        let consumer = rx_channel
+            .basic_consume(
+                &routing_key,
+                format!("{consumer_tag}-{routing_key}").as_str(),
+                BasicConsumeOptions::default(),
+                FieldTable::default(),
+            )
+            .await
+            .unwrap();
+        consumers.push(consumer);
+    }
+    let mut consumer = select_all(consumers);
+
     let tx_results = conn.create_channel().await.unwrap();
     tx_results
         .queue_declare(
@@ -61,35 +99,72 @@ async fn main() {
         .await
         .unwrap();
 
+    let tx_logs = conn.create_channel().await.unwrap();
+
+    let running_builds: RunningBuilds = Arc::new(Mutex::new(HashMap::new()));
+    start_heartbeat(
+        conn.create_channel().await.unwrap(),
+        &config,
+        concurrent_builds,
+        running_builds.clone(),
+    )
+    .await;
+
+    let build_slots = Arc::new(Semaphore::new(concurrent_builds));
+
     while let Some(delivery) = consumer.next().await {
         let delivery = delivery.expect("error in consumer");
-        let raw_data = match std::str::from_utf8(&*delivery.data) {
+        let raw_data = match std::str::from_utf8(&delivery.data) {
             Ok(v) => v.to_string(),
             Err(e) => panic!("Invalid UTF-8 sequence: {}", e),
         };
         let build_task: BuildTaskTransmissionFormat = serde_json::from_str(raw_data.as_str()).unwrap();
 
-        match build_package(&build_task).await {
-            Ok(results) => {
-                delivery.ack(BasicAckOptions::default()).await.expect("ack");
-                if !results.success {
-                    warn!("Failed to build package '{}': {}", results.task.name, get_error_descriptions(results.status_code));
+        let permit = build_slots.clone().acquire_owned().await.unwrap();
+        let tx_results = tx_results.clone();
+        let tx_logs = tx_logs.clone();
+        let running_builds = running_builds.clone();
+        let delivery_tag = delivery.delivery_tag;
+        let build_id = match &build_task.variant {
+            Some(variant) => format!("{}-{variant}-{}", build_task.name, get_rand_string()),
+            None => format!("{}-{}", build_task.name, get_rand_string()),
+        };
+        running_builds.lock().unwrap().insert(
+            delivery_tag,
+            RunningBuild {
+                build_id: build_id.clone(),
+                package_id: build_task.id,
+                name: build_task.name.clone(),
+                variant: build_task.variant.clone(),
+                version: build_task.version.clone(),
+                started_at: Utc::now().naive_utc(),
+            },
+        );
+        tokio::spawn(async move {
+            match build_package(&build_task, &build_id, &tx_logs).await {
+                Ok(results) => {
+                    delivery.ack(BasicAckOptions::default()).await.expect("ack");
+                    if !results.success {
+                        warn!("Failed to build package '{}': {}", results.task.display_name(), get_error_descriptions(results.status_code));
+                    }
+                    tx_results.basic_publish(
+                        "",
+                        "build_results",
+                        BasicPublishOptions::default(),
+                        serde_json::to_string(&results).unwrap().as_ref(),
+                        BasicProperties::default(),
+                    ).await.unwrap();
+                }
+                Err(error) => {
+                    error!("System error while building package '{}':\n{:?}", build_task.display_name(), error);
+                    delivery
+                        .nack(BasicNackOptions::default())
+                        .await
+                        .expect("nack");
                 }
-                tx_results.basic_publish(
-                    "",
-                    "build_results",
-                    BasicPublishOptions::default(),
-                    serde_json::to_string(&results).unwrap().as_ref(),
-                    BasicProperties::default(),
-                ).await.unwrap();
-            }
-            Err(error) => {
-                error!("System error while building package '{}':\n{:?}", build_task.name, error);
-                delivery
-                    .nack(BasicNackOptions::default())
-                    .await
-                    .expect("nack");
             }
-        }
+            running_builds.lock().unwrap().remove(&delivery_tag);
+            drop(permit);
+        });
     }
 }
@@ -0,0 +1,106 @@
+use crate::publish::{Publisher, file_name, file_sha256, new_upload};
+use crate::sign::GpgSigner;
+use common::config::DirectorySettings;
+use common::types::ArtifactUpload;
+use repo_db::Repository;
+use repo_db::package::is_package_file;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Copies packages into a local directory, e.g. a mounted pacman repository
+pub struct DirectoryPublisher {
+    name: String,
+    settings: DirectorySettings,
+    signer: Option<Arc<GpgSigner>>,
+}
+
+impl DirectoryPublisher {
+    pub fn new(
+        name: String,
+        settings: DirectorySettings,
+        signer: Option<Arc<GpgSigner>>,
+    ) -> DirectoryPublisher {
+        DirectoryPublisher {
+            name,
+            settings,
+            signer,
+        }
+    }
+
+    /// Copies the file next to its target first, so readers never see a partially written package
+    async fn copy(&self, file: &Path, sha256: &str) -> Result<PathBuf, String> {
+        let dir = Path::new(&self.settings.path);
+        tokio::fs::create_dir_all(dir)
+            .await
+            .map_err(|e| e.to_string())?;
+        let name = file_name(file);
+        let target = dir.join(&name);
+        let partial = dir.join(format!(".{name}.part"));
+
+        tokio::fs::copy(file, &partial)
+            .await
+            .map_err(|e| e.to_string())?;
+        let checksum = file_sha256(partial.clone())
+            .await
+            .map_err(|e| e.to_string())?;
+        if checksum != sha256 {
+            let _ = tokio::fs::remove_file(&partial).await;
+            return Err(format!("checksum of the copy is {checksum}, expected {sha256}"));
+        }
+        tokio::fs::rename(&partial, &target)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(target)
+    }
+
+    /// Adds the copied package to the repository database, if one is configured.
+    ///
+    /// Signatures are only copied, they are added to the database together with their package.
+    async fn add_to_repo(&self, target: PathBuf) -> Result<Option<String>, String> {
+        let Some(repo) = &self.settings.repo else {
+            return Ok(None);
+        };
+        if !is_package_file(&file_name(&target)) {
+            return Ok(None);
+        }
+        let mut repository = Repository::new(&self.settings.path, repo)
+            .keep_versions(self.settings.keep_versions.unwrap_or_default());
+        if let Some(signer) = &self.signer {
+            repository = repository.signer(signer.clone());
+        }
+        tokio::task::spawn_blocking(move || repository.add(&[target]))
+            .await
+            .map_err(|e| e.to_string())?
+            .map_err(|e| e.to_string())?;
+        Ok(Some(repo.clone()))
+    }
+}
+
+#[async_trait::async_trait]
+impl Publisher for DirectoryPublisher {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn publish(&self, file: &Path, sha256: &str) -> ArtifactUpload {
+        let mut upload = new_upload(&self.name, file, sha256);
+        upload.attempts = 1;
+        let target = match self.copy(file, sha256).await {
+            Ok(target) => target,
+            Err(e) => {
+                upload.response = e;
+                return upload;
+            }
+        };
+        upload.response = format!("copied to {}", target.display());
+        match self.add_to_repo(target).await {
+            Ok(Some(repo)) => {
+                upload.success = true;
+                upload.response.push_str(&format!(", added to {repo}"));
+            }
+            Ok(None) => upload.success = true,
+            Err(e) => upload.response.push_str(&format!(", adding to the repository failed: {e}")),
+        }
+        upload
+    }
+}
@@ -0,0 +1,53 @@
+use crate::publish::{PublishError, Publisher, file_body, new_upload, send_with_retries};
+use common::config::GiteaSettings;
+use common::types::ArtifactUpload;
+use reqwest::header::CONTENT_LENGTH;
+use reqwest::{Client, StatusCode};
+use std::path::Path;
+
+/// Uploads packages to the Arch package registry of Gitea
+pub struct GiteaPublisher {
+    name: String,
+    settings: GiteaSettings,
+    client: Client,
+}
+
+impl GiteaPublisher {
+    pub fn new(name: String, settings: GiteaSettings) -> GiteaPublisher {
+        GiteaPublisher {
+            name,
+            settings,
+            client: Client::new(),
+        }
+    }
+
+    async fn send(&self, file: &Path) -> Result<reqwest::Response, PublishError> {
+        let (body, length) = file_body(file).await?;
+        let response = self
+            .client
+            .put(&self.settings.repo)
+            .basic_auth(&self.settings.user, Some(&self.settings.token))
+            .header(CONTENT_LENGTH, length)
+            .body(body)
+            .send()
+            .await?;
+        Ok(response)
+    }
+}
+
+#[async_trait::async_trait]
+impl Publisher for GiteaPublisher {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn publish(&self, file: &Path, sha256: &str) -> ArtifactUpload {
+        let upload = new_upload(&self.name, file, sha256);
+        send_with_retries(upload, |s| s == StatusCode::CREATED, || self.send(file)).await
+    }
+
+    /// Gitea signs its repository itself and only accepts package files
+    fn accepts_signatures(&self) -> bool {
+        false
+    }
+}
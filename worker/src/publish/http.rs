@@ -0,0 +1,55 @@
+use crate::publish::{
+    PublishError, Publisher, file_body, file_name, new_upload, send_with_retries,
+};
+use common::config::HttpSettings;
+use common::types::ArtifactUpload;
+use reqwest::Client;
+use reqwest::header::CONTENT_LENGTH;
+use std::path::Path;
+
+/// Uploads packages with `PUT` requests, e.g. to a WebDAV share
+pub struct HttpPublisher {
+    name: String,
+    settings: HttpSettings,
+    client: Client,
+}
+
+impl HttpPublisher {
+    pub fn new(name: String, settings: HttpSettings) -> HttpPublisher {
+        HttpPublisher {
+            name,
+            settings,
+            client: Client::new(),
+        }
+    }
+
+    async fn send(&self, file: &Path) -> Result<reqwest::Response, PublishError> {
+        let (body, length) = file_body(file).await?;
+        let url = format!(
+            "{}/{}",
+            self.settings.url.trim_end_matches('/'),
+            file_name(file)
+        );
+        let mut request = self
+            .client
+            .put(url)
+            .header(CONTENT_LENGTH, length)
+            .body(body);
+        if let Some(user) = &self.settings.user {
+            request = request.basic_auth(user, self.settings.password.as_ref());
+        }
+        Ok(request.send().await?)
+    }
+}
+
+#[async_trait::async_trait]
+impl Publisher for HttpPublisher {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn publish(&self, file: &Path, sha256: &str) -> ArtifactUpload {
+        let upload = new_upload(&self.name, file, sha256);
+        send_with_retries(upload, |s| s.is_success(), || self.send(file)).await
+    }
+}
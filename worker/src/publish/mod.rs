@@ -0,0 +1,185 @@
+use common::config::{PublisherSettings, WorkerConfig};
+use crate::sign::GpgSigner;
+use common::types::ArtifactUpload;
+use reqwest::{Response, StatusCode};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::sleep;
+
+pub mod directory;
+pub mod gitea;
+pub mod http;
+pub mod s3;
+
+const UPLOAD_ATTEMPTS: u32 = 3;
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+type PublishError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A destination built packages can be published to
+#[async_trait::async_trait]
+pub trait Publisher: Send + Sync {
+    /// Name of the destination in the config
+    fn name(&self) -> &str;
+
+    /// Publishes a single package or signature file, `sha256` is the verified checksum of the file
+    async fn publish(&self, file: &Path, sha256: &str) -> ArtifactUpload;
+
+    /// Whether detached `.sig` files are published next to the packages
+    fn accepts_signatures(&self) -> bool {
+        true
+    }
+}
+
+fn create_publisher(
+    name: &str,
+    settings: &PublisherSettings,
+    signer: Option<Arc<GpgSigner>>,
+) -> Box<dyn Publisher> {
+    let name = name.to_string();
+    match settings {
+        PublisherSettings::Gitea(s) => Box::new(gitea::GiteaPublisher::new(name, s.clone())),
+        PublisherSettings::Directory(s) => {
+            Box::new(directory::DirectoryPublisher::new(name, s.clone(), signer))
+        }
+        PublisherSettings::S3(s) => Box::new(s3::S3Publisher::new(name, s.clone())),
+        PublisherSettings::Http(s) => Box::new(http::HttpPublisher::new(name, s.clone())),
+    }
+}
+
+/// Creates the publishers a package is published to.
+///
+/// If `publish_to` is missing, all configured publishers are used, and it's an error if there are none.
+/// An empty `publish_to` explicitly builds without publishing.
+/// The legacy `gitea` setting is available as the publisher `gitea`.
+/// Repository databases written by the publishers are signed with `signer`.
+pub fn get_publishers(
+    config: &WorkerConfig,
+    publish_to: &Option<Vec<String>>,
+    signer: Option<Arc<GpgSigner>>,
+) -> Result<Vec<Box<dyn Publisher>>, String> {
+    let mut configured: HashMap<String, PublisherSettings> =
+        config.publishers.clone().unwrap_or_default();
+    if let Some(gitea) = &config.gitea {
+        configured
+            .entry("gitea".to_string())
+            .or_insert(PublisherSettings::Gitea(gitea.clone()));
+    }
+
+    let names = match publish_to {
+        Some(names) if names.is_empty() => {
+            warn!("The package is built without publishing it, its publish_to list is empty");
+            Vec::new()
+        }
+        Some(names) => names.clone(),
+        None if configured.is_empty() => {
+            return Err("no publishers are configured on this worker, the packages would be lost".to_string());
+        }
+        None => {
+            let mut names: Vec<String> = configured.keys().cloned().collect();
+            names.sort();
+            names
+        }
+    };
+
+    names
+        .iter()
+        .map(|name| match configured.get(name) {
+            Some(settings) => Ok(create_publisher(name, settings, signer.clone())),
+            None => Err(format!("publisher '{name}' is not configured on this worker")),
+        })
+        .collect()
+}
+
+/// Calculates the hex encoded SHA-256 checksum of a file without loading it into memory
+pub async fn file_sha256(path: PathBuf) -> std::io::Result<String> {
+    tokio::task::spawn_blocking(move || {
+        let mut hasher = Sha256::new();
+        let mut file = std::fs::File::open(path)?;
+        std::io::copy(&mut file, &mut hasher)?;
+        Ok(to_hex(&hasher.finalize()))
+    })
+    .await?
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn new_upload(destination: &str, file: &Path, sha256: &str) -> ArtifactUpload {
+    ArtifactUpload {
+        destination: destination.to_string(),
+        file: file_name(file),
+        sha256: sha256.to_string(),
+        attempts: 0,
+        status: None,
+        response: String::new(),
+        success: false,
+    }
+}
+
+fn file_name(file: &Path) -> String {
+    file.file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Returns whether an upload that failed with `status` may succeed when retried
+fn is_retryable(status: StatusCode) -> bool {
+    status.is_server_error()
+        || status == StatusCode::REQUEST_TIMEOUT
+        || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// Sends an upload request until it succeeds, retrying on network and server errors
+async fn send_with_retries<F, Fut>(
+    mut upload: ArtifactUpload,
+    is_success: impl Fn(StatusCode) -> bool,
+    send: F,
+) -> ArtifactUpload
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<Response, PublishError>>,
+{
+    while upload.attempts < UPLOAD_ATTEMPTS {
+        upload.attempts += 1;
+        match send().await {
+            Ok(response) => {
+                let status = response.status();
+                upload.status = Some(status.as_u16());
+                upload.response = response.text().await.unwrap_or_default();
+                if is_success(status) {
+                    upload.success = true;
+                    return upload;
+                }
+                if !is_retryable(status) {
+                    return upload;
+                }
+            }
+            Err(e) => {
+                upload.status = None;
+                upload.response = e.to_string();
+            }
+        }
+        warn!(
+            "Publishing {} to {} failed (attempt {}/{UPLOAD_ATTEMPTS}): {:?} {}",
+            upload.file, upload.destination, upload.attempts, upload.status, upload.response
+        );
+        if upload.attempts < UPLOAD_ATTEMPTS {
+            sleep(RETRY_DELAY * upload.attempts).await;
+        }
+    }
+
+    upload
+}
+
+/// Opens a file as a streamed request body and returns its length
+async fn file_body(file: &Path) -> Result<(reqwest::Body, u64), PublishError> {
+    let file = tokio::fs::File::open(file).await?;
+    let length = file.metadata().await?.len();
+    Ok((reqwest::Body::from(file), length))
+}
@@ -0,0 +1,94 @@
+use crate::publish::{PublishError, Publisher, file_body, file_name, new_upload, send_with_retries};
+use common::config::S3Settings;
+use common::sigv4::{Credentials, SignedRequest, authorization, uri_encode};
+use common::types::ArtifactUpload;
+use reqwest::header::{AUTHORIZATION, CONTENT_LENGTH};
+use reqwest::{Client, Url};
+use sea_orm::sqlx::types::chrono::Utc;
+use std::path::Path;
+
+const DEFAULT_REGION: &str = "us-east-1";
+/// The body is streamed, so it is not part of the signature
+const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+
+/// Uploads packages to an S3 compatible object storage, using path style URLs
+pub struct S3Publisher {
+    name: String,
+    settings: S3Settings,
+    client: Client,
+}
+
+impl S3Publisher {
+    pub fn new(name: String, settings: S3Settings) -> S3Publisher {
+        S3Publisher {
+            name,
+            settings,
+            client: Client::new(),
+        }
+    }
+
+    /// Returns the URL of the object and the value of the `Authorization` header
+    fn sign(&self, key: &str, amz_date: &str) -> Result<(String, String), PublishError> {
+        let endpoint = Url::parse(&self.settings.endpoint)?;
+        let mut host = endpoint.host_str().ok_or("S3 endpoint has no host")?.to_string();
+        if let Some(port) = endpoint.port() {
+            host = format!("{host}:{port}");
+        }
+        let base_path = endpoint.path().trim_end_matches('/');
+        let path = format!("{base_path}/{}/{key}", self.settings.bucket);
+        let request = SignedRequest {
+            method: "PUT",
+            path: &path,
+            headers: &[
+                ("host", &host),
+                ("x-amz-content-sha256", UNSIGNED_PAYLOAD),
+                ("x-amz-date", amz_date),
+            ],
+            payload_hash: UNSIGNED_PAYLOAD,
+        };
+        let credentials = Credentials {
+            access_key: &self.settings.access_key,
+            secret_key: &self.settings.secret_key,
+            region: self.settings.region.as_deref().unwrap_or(DEFAULT_REGION),
+            service: "s3",
+        };
+
+        let url = format!("{}://{host}{}", endpoint.scheme(), uri_encode(&path));
+        Ok((url, authorization(&request, &credentials, amz_date)))
+    }
+
+    async fn send(&self, file: &Path) -> Result<reqwest::Response, PublishError> {
+        let key = format!(
+            "{}{}",
+            self.settings.prefix.clone().unwrap_or_default(),
+            file_name(file)
+        );
+        // the signature is only valid for a few minutes, so sign each attempt
+        let amz_date = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let (url, authorization) = self.sign(&key, &amz_date)?;
+        let (body, length) = file_body(file).await?;
+        let response = self
+            .client
+            .put(url)
+            .header(AUTHORIZATION, authorization)
+            .header("x-amz-content-sha256", UNSIGNED_PAYLOAD)
+            .header("x-amz-date", amz_date)
+            .header(CONTENT_LENGTH, length)
+            .body(body)
+            .send()
+            .await?;
+        Ok(response)
+    }
+}
+
+#[async_trait::async_trait]
+impl Publisher for S3Publisher {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn publish(&self, file: &Path, sha256: &str) -> ArtifactUpload {
+        let upload = new_upload(&self.name, file, sha256);
+        send_with_retries(upload, |s| s.is_success(), || self.send(file)).await
+    }
+}
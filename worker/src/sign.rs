@@ -0,0 +1,108 @@
+use common::config::SigningSettings;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use tempfile::TempDir;
+
+/// Creates detached OpenPGP signatures with `gpg`, using a keyring that only contains the configured key
+pub struct GpgSigner {
+    home: TempDir,
+    fingerprint: String,
+    passphrase: Option<String>,
+}
+
+fn run_gpg(home: &Path, args: &[&str], input: Option<&str>) -> Result<String, String> {
+    let mut child = Command::new("gpg")
+        .arg("--homedir")
+        .arg(home)
+        .args(["--batch", "--no-tty", "--yes"])
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to run gpg: {e}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(input.unwrap_or_default().as_bytes())
+            .map_err(|e| e.to_string())?;
+    }
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!(
+            "gpg failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+impl GpgSigner {
+    /// Imports the key file into a temporary keyring
+    pub fn new(settings: &SigningSettings) -> Result<GpgSigner, String> {
+        let home = tempfile::tempdir().map_err(|e| e.to_string())?;
+        run_gpg(home.path(), &["--import", &settings.key_file], None)?;
+
+        let keys = run_gpg(
+            home.path(),
+            &["--with-colons", "--list-secret-keys"],
+            None,
+        )?;
+        // the first fingerprint after the `sec` record is the one of the primary key
+        let fingerprint = keys
+            .lines()
+            .skip_while(|l| !l.starts_with("sec:"))
+            .find(|l| l.starts_with("fpr:"))
+            .and_then(|l| l.split(':').nth(9))
+            .filter(|f| !f.is_empty())
+            .ok_or_else(|| format!("{} contains no secret key", settings.key_file))?
+            .to_string();
+
+        Ok(GpgSigner {
+            home,
+            fingerprint,
+            passphrase: settings.passphrase.clone(),
+        })
+    }
+
+    pub fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+
+    /// Writes the binary detached signature of `file` to `signature`
+    pub fn sign(&self, file: &Path, signature: &Path) -> Result<(), String> {
+        let file = file.to_string_lossy();
+        let signature = signature.to_string_lossy();
+        let mut args = vec![
+            "--local-user",
+            &self.fingerprint,
+            "--detach-sign",
+            "--no-armor",
+            "--output",
+            &signature,
+        ];
+        if self.passphrase.is_some() {
+            args.extend(["--pinentry-mode", "loopback", "--passphrase-fd", "0"]);
+        }
+        args.push(&file);
+        run_gpg(self.home.path(), &args, self.passphrase.as_deref())?;
+        Ok(())
+    }
+}
+
+impl Drop for GpgSigner {
+    /// Stops the agent started for the temporary keyring
+    fn drop(&mut self) {
+        let _ = Command::new("gpgconf")
+            .arg("--homedir")
+            .arg(self.home.path())
+            .args(["--kill", "gpg-agent"])
+            .output();
+    }
+}
+
+impl repo_db::Signer for GpgSigner {
+    fn sign(&self, file: &Path, signature: &Path) -> Result<(), String> {
+        GpgSigner::sign(self, file, signature)
+    }
+}